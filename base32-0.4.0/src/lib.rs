@@ -4,27 +4,48 @@
 extern crate quickcheck;
 #[cfg(test)]
 extern crate rand;
+#[cfg(test)]
+#[macro_use]
+extern crate std;
 
 extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::cmp::min;
 
-#[derive(Copy,Clone)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
 pub enum Alphabet {
     RFC4648 { padding: bool },
+    /// RFC4648 section 7 "base32hex", whose encodings sort like the data they encode.
+    RFC4648Hex { padding: bool },
     Crockford,
+    /// Crockford followed by its check symbol, which catches any single mistyped character.
+    /// The check symbol is the value of the other symbols, read as a base32 number, modulo 37.
+    CrockfordCheck,
+    /// z-base-32, lowercase and ordered to be easier to read out and type.
+    ZBase32,
 }
 
 const RFC4648_ALPHABET: &'static [u8]   = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const RFC4648_HEX_ALPHABET: &'static [u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
 const CROCKFORD_ALPHABET: &'static [u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CROCKFORD_CHECK_SYMBOLS: &'static [u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+const ZBASE32_ALPHABET: &'static [u8]   = b"ybndrfg8ejkmcpqxot1uwisza345h769";
 
 pub fn encode(alphabet: Alphabet, data: &[u8]) -> String {
     let (alphabet, padding) = match alphabet {
         Alphabet::RFC4648 { padding } => (RFC4648_ALPHABET, padding),
+        Alphabet::RFC4648Hex { padding } => (RFC4648_HEX_ALPHABET, padding),
         Alphabet::Crockford => (CROCKFORD_ALPHABET, false),
+        Alphabet::CrockfordCheck => {
+            let mut ret = encode(Alphabet::Crockford, data);
+            let (checksum, _) = crockford_checksum(ret.as_bytes());
+            ret.push(CROCKFORD_CHECK_SYMBOLS[checksum as usize] as char);
+            return ret;
+        }
+        Alphabet::ZBase32 => (ZBASE32_ALPHABET, false),
     };
-    let mut ret = Vec::with_capacity((data.len()+3)/4*5);
+    let mut ret = Vec::with_capacity((data.len()+4)/5*8);
 
     for chunk in data.chunks(5) {
         let buf = {
@@ -60,17 +81,30 @@ pub fn encode(alphabet: Alphabet, data: &[u8]) -> String {
 }
 
 const RFC4648_INV_ALPHABET: [i8; 43] = [-1, -1, 26, 27, 28, 29, 30, 31, -1, -1, -1, -1, -1, 0, -1, -1, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25];
+const RFC4648_HEX_INV_ALPHABET: [i8; 43] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, -1, -1, -1, 0, -1, -1, -1, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, -1, -1, -1, -1];
 const CROCKFORD_INV_ALPHABET: [i8; 43] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, -1, -1, -1, -1, -1, -1, -1, 10, 11, 12, 13, 14, 15, 16, 17, 1, 18, 19, 1, 20, 21, 0, 22, 23, 24, 25, 26, -1, 27, 28, 29, 30, 31];
+const ZBASE32_INV_ALPHABET: [i8; 43] = [-1, 18, -1, 25, 26, 27, 30, 29, 7, 31, -1, -1, -1, -1, -1, -1, -1, 24, 1, 12, 3, 8, 5, 6, 28, 21, 9, 10, -1, 11, 2, 16, 13, 14, 4, 22, 17, 19, -1, 20, 15, 0, 23];
 
 pub fn decode(alphabet: Alphabet, data: &str) -> Option<Vec<u8>> {
     if !data.is_ascii() {
         return None;
     }
-    let data = data.as_bytes();
     let alphabet = match alphabet {
         Alphabet::RFC4648 {..} => RFC4648_INV_ALPHABET,
-        Alphabet::Crockford => CROCKFORD_INV_ALPHABET
+        Alphabet::RFC4648Hex {..} => RFC4648_HEX_INV_ALPHABET,
+        Alphabet::Crockford => CROCKFORD_INV_ALPHABET,
+        Alphabet::CrockfordCheck => {
+            // ASCII, so the last character is the last byte
+            let (body, check) = data.split_at(data.len().checked_sub(1)?);
+            let ret = decode(Alphabet::Crockford, body)?;
+            if crockford_check_matches(body.as_bytes(), check.as_bytes()[0]) == 0 {
+                return None;
+            }
+            return Some(ret);
+        }
+        Alphabet::ZBase32 => ZBASE32_INV_ALPHABET,
     };
+    let data = data.as_bytes();
     let mut unpadded_data_length = data.len();
     for i in 1..min(6, data.len())+1 {
         if data[data.len() - i] != b'=' {
@@ -101,15 +135,397 @@ pub fn decode(alphabet: Alphabet, data: &str) -> Option<Vec<u8>> {
     Some(ret)
 }
 
+/// How forgiving `try_decode` is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Accepts exactly what `decode` does: any case, Crockford's `I`, `L` and `O` aliases,
+    /// up to 6 trailing `=` whether or not the alphabet pads, `=` for `A` elsewhere in RFC4648,
+    /// and lengths no encoding has, whose leftover bits are dropped.
+    Lenient,
+    /// Accepts only what `encode` outputs with the same alphabet, so each byte string has
+    /// exactly one encoding.
+    Strict,
+}
+
+/// Why `try_decode` failed. Positions are byte offsets into the input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The byte at `position` is not part of the alphabet.
+    InvalidCharacter { position: usize, byte: u8 },
+    /// Padding is missing at `position`, or shouldn't be there.
+    InvalidPadding { position: usize },
+    /// No byte string encodes to `length` characters, not counting padding.
+    InvalidLength { length: usize },
+    /// The last character, at `position`, has bits set that don't belong to any byte.
+    TrailingBits { position: usize },
+    /// The check symbol, at `position`, doesn't match the rest of the input.
+    CheckSymbol { position: usize },
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            DecodeError::InvalidCharacter { position, byte } if byte.is_ascii_graphic() =>
+                write!(f, "invalid character '{}' at position {}", byte as char, position),
+            DecodeError::InvalidCharacter { position, byte } =>
+                write!(f, "invalid byte {:#04x} at position {}", byte, position),
+            DecodeError::InvalidPadding { position } =>
+                write!(f, "invalid padding at position {}", position),
+            DecodeError::InvalidLength { length } =>
+                write!(f, "invalid length, nothing encodes to {} characters", length),
+            DecodeError::TrailingBits { position } =>
+                write!(f, "non-zero trailing bits in the character at position {}", position),
+            DecodeError::CheckSymbol { position } =>
+                write!(f, "check symbol at position {} doesn't match", position),
+        }
+    }
+}
+
+/// Same as `decode`, but says what is wrong with the input, and can insist on canonical input.
+pub fn try_decode(alphabet: Alphabet, data: &str, mode: Mode) -> Result<Vec<u8>, DecodeError> {
+    try_decode_bytes(alphabet, data.as_bytes(), mode)
+}
+
+fn try_decode_bytes(alphabet: Alphabet, data: &[u8], mode: Mode) -> Result<Vec<u8>, DecodeError> {
+    let (forward, inverse, padding) = match alphabet {
+        Alphabet::RFC4648 { padding } => (RFC4648_ALPHABET, &RFC4648_INV_ALPHABET, padding),
+        Alphabet::RFC4648Hex { padding } => (RFC4648_HEX_ALPHABET, &RFC4648_HEX_INV_ALPHABET, padding),
+        Alphabet::Crockford => (CROCKFORD_ALPHABET, &CROCKFORD_INV_ALPHABET, false),
+        Alphabet::CrockfordCheck => {
+            let (&check, body) = match data.split_last() {
+                Some(split) => split,
+                None => return Err(DecodeError::InvalidLength { length: 0 }),
+            };
+            let ret = try_decode_bytes(Alphabet::Crockford, body, mode)?;
+            let position = body.len();
+            let valid = match mode {
+                Mode::Lenient => crockford_check_value(ct_to_upper(check)).1 != 0,
+                Mode::Strict => CROCKFORD_CHECK_SYMBOLS.contains(&check),
+            };
+            if !valid {
+                return Err(DecodeError::InvalidCharacter { position, byte: check });
+            }
+            if crockford_check_matches(body, check) == 0 {
+                return Err(DecodeError::CheckSymbol { position });
+            }
+            return Ok(ret);
+        }
+        Alphabet::ZBase32 => (ZBASE32_ALPHABET, &ZBASE32_INV_ALPHABET, false),
+    };
+    let trailing = data.iter().rev().take_while(|&&c| c == b'=').count();
+    let length = match mode {
+        Mode::Lenient => data.len() - min(6, trailing),
+        Mode::Strict => data.len() - trailing,
+    };
+
+    let mut ret = Vec::with_capacity(length*5/8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for (position, &c) in data.iter().enumerate() {
+        let value = match mode {
+            Mode::Lenient => match inverse.get(c.to_ascii_uppercase().wrapping_sub(b'0') as usize) {
+                Some(&-1) | None => None,
+                Some(&value) => Some(value as u8),
+            },
+            Mode::Strict if position >= length => continue,
+            Mode::Strict => forward.iter().position(|&a| a == c).map(|value| value as u8),
+        };
+        let value = match value {
+            Some(value) => value,
+            None if c == b'=' => return Err(DecodeError::InvalidPadding { position }),
+            None => return Err(DecodeError::InvalidCharacter { position, byte: c }),
+        };
+        if position >= length {
+            continue;
+        }
+        buffer = buffer << 5 | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            ret.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if mode == Mode::Strict {
+        if trailing > 0 && !padding {
+            return Err(DecodeError::InvalidPadding { position: length });
+        }
+        if let 1 | 3 | 6 = length % 8 {
+            return Err(DecodeError::InvalidLength { length });
+        }
+        let padded_length = (length+7)/8*8;
+        if padding && data.len() != padded_length {
+            return Err(DecodeError::InvalidPadding { position: min(data.len(), padded_length) });
+        }
+        if buffer != 0 {
+            return Err(DecodeError::TrailingBits { position: length - 1 });
+        }
+    }
+    Ok(ret)
+}
+
+/// Number of characters `encode` outputs for `len` bytes.
+pub fn encoded_len(alphabet: Alphabet, len: usize) -> usize {
+    match alphabet {
+        Alphabet::RFC4648 { padding: true } | Alphabet::RFC4648Hex { padding: true } => (len+4)/5*8,
+        Alphabet::CrockfordCheck => (len*8+4)/5 + 1,
+        _ => (len*8+4)/5,
+    }
+}
+
+/// Same as `encode`, into `out` rather than a new `String`.
+///
+/// The time it takes depends on the length of `data`, not on its contents. Returns the number
+/// of characters written, or `None` if `out` is shorter than `encoded_len`.
+pub fn encode_into(alphabet: Alphabet, data: &[u8], out: &mut [u8]) -> Option<usize> {
+    let len = encoded_len(alphabet, data.len());
+    if out.len() < len {
+        return None;
+    }
+    let symbol = match alphabet {
+        Alphabet::RFC4648 { .. } => rfc4648_symbol,
+        Alphabet::RFC4648Hex { .. } => rfc4648_hex_symbol,
+        Alphabet::Crockford => crockford_symbol,
+        Alphabet::CrockfordCheck => {
+            encode_into(Alphabet::Crockford, data, out)?;
+            let (checksum, _) = crockford_checksum(&out[..len-1]);
+            out[len-1] = ct_lookup(CROCKFORD_CHECK_SYMBOLS, checksum);
+            return Some(len);
+        }
+        Alphabet::ZBase32 => zbase32_symbol,
+    };
+
+    let mut written = 0;
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &b in data {
+        buffer = buffer << 8 | b as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out[written] = symbol((buffer >> bits) as u8 & 0x1F);
+            written += 1;
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out[written] = symbol((buffer << (5 - bits)) as u8 & 0x1F);
+        written += 1;
+    }
+    for c in &mut out[written..len] {
+        *c = b'=';
+    }
+    Some(len)
+}
+
+/// Same as `decode`, into `out` rather than a new `Vec`.
+///
+/// The time it takes depends on the length of `data` and on how much of it is padding, not on
+/// the characters of the secret: there is no lookup table, and an invalid character doesn't
+/// stop decoding early. Returns the number of bytes written, at most `data.len() * 5 / 8`, or
+/// `None` if `data` is invalid, in which case `out` is left zeroed, or if `out` is too short.
+pub fn decode_into(alphabet: Alphabet, data: &str, out: &mut [u8]) -> Option<usize> {
+    decode_into_bytes(alphabet, data.as_bytes(), out)
+}
+
+fn decode_into_bytes(alphabet: Alphabet, data: &[u8], out: &mut [u8]) -> Option<usize> {
+    if let Alphabet::CrockfordCheck = alphabet {
+        let (&check, body) = data.split_last()?;
+        let len = decode_into_bytes(Alphabet::Crockford, body, out)?;
+        if crockford_check_matches(body, check) == 0 {
+            for b in &mut out[..len] {
+                *b = 0;
+            }
+            return None;
+        }
+        return Some(len);
+    }
+    let mut padding = 0;
+    let mut trailing = 1;
+    for &c in data.iter().rev().take(6) {
+        trailing &= ct_in_range(c, b'=', b'=');
+        padding += (trailing & 1) as usize;
+    }
+    let len = (data.len()-padding)*5/8;
+    if out.len() < len {
+        return None;
+    }
+    let value = match alphabet {
+        Alphabet::RFC4648 { .. } => rfc4648_value,
+        Alphabet::RFC4648Hex { .. } => rfc4648_hex_value,
+        Alphabet::Crockford | Alphabet::CrockfordCheck => crockford_value,
+        Alphabet::ZBase32 => zbase32_value,
+    };
+
+    let mut invalid = 0u8;
+    let mut written = 0;
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &c in data {
+        let (v, valid) = value(ct_to_upper(c));
+        invalid |= !valid;
+        buffer = buffer << 5 | v as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            if written < len {
+                out[written] = (buffer >> bits) as u8;
+                written += 1;
+            }
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if invalid != 0 {
+        for b in &mut out[..len] {
+            *b = 0;
+        }
+        return None;
+    }
+    Some(len)
+}
+
+/// `0xFF` if `lo <= c <= hi`, else `0`, without branching on `c`.
+fn ct_in_range(c: u8, lo: u8, hi: u8) -> u8 {
+    let c = c as i16;
+    (((lo as i16 - 1 - c) & (c - hi as i16 - 1)) >> 8) as u8
+}
+
+/// `1` if `v >= k`, else `0`, without branching on `v`.
+fn ct_at_least(v: u8, k: u8) -> u8 {
+    (((k as i16 - 1 - v as i16) >> 8) & 1) as u8
+}
+
+fn ct_to_upper(c: u8) -> u8 {
+    c & !(ct_in_range(c, b'a', b'z') & 0x20)
+}
+
+/// `table[i]`, reading every entry so that the time taken doesn't depend on `i`
+fn ct_lookup(table: &[u8], i: u8) -> u8 {
+    let mut ret = 0;
+    for (j, &c) in table.iter().enumerate() {
+        ret |= ct_in_range(i, j as u8, j as u8) & c;
+    }
+    ret
+}
+
+/// Where the uppercase `c` is in `table`, ignoring case, and `0xFF` if it is there at all
+fn ct_index(table: &[u8], c: u8) -> (u8, u8) {
+    let mut index = 0;
+    let mut found = 0;
+    for (j, &t) in table.iter().enumerate() {
+        let t = t.to_ascii_uppercase();
+        let mask = ct_in_range(c, t, t);
+        index |= mask & j as u8;
+        found |= mask;
+    }
+    (index, found)
+}
+
+fn rfc4648_symbol(v: u8) -> u8 {
+    // A-Z, then 2-7
+    v + b'A' - ct_at_least(v, 26)*(b'A' + 26 - b'2')
+}
+
+fn rfc4648_hex_symbol(v: u8) -> u8 {
+    // 0-9, then A-V
+    v + b'0' + ct_at_least(v, 10)*7
+}
+
+fn zbase32_symbol(v: u8) -> u8 {
+    ct_lookup(ZBASE32_ALPHABET, v)
+}
+
+fn crockford_symbol(v: u8) -> u8 {
+    // 0-9, then A-Z skipping I, L, O and U
+    v + b'0' + ct_at_least(v, 10)*7 + ct_at_least(v, 18) + ct_at_least(v, 20) + ct_at_least(v, 22) + ct_at_least(v, 27)
+}
+
+/// Value of an uppercase character, and `0xFF` if it has one
+fn rfc4648_value(c: u8) -> (u8, u8) {
+    // `=` decodes as `A`, like the lookup table in `decode` does
+    let ranges = [(b'A', b'Z', 0), (b'2', b'7', 26), (b'=', b'=', 0)];
+    ct_value(c, &ranges)
+}
+
+/// Value of an uppercase character, and `0xFF` if it has one
+fn rfc4648_hex_value(c: u8) -> (u8, u8) {
+    // `=` decodes as `0`, like RFC4648 decodes it as `A`
+    let ranges = [(b'0', b'9', 0), (b'A', b'V', 10), (b'=', b'=', 0)];
+    ct_value(c, &ranges)
+}
+
+/// Value of an uppercase character, and `0xFF` if it has one
+fn zbase32_value(c: u8) -> (u8, u8) {
+    ct_index(ZBASE32_ALPHABET, c)
+}
+
+/// Value of an uppercase character, and `0xFF` if it has one
+fn crockford_value(c: u8) -> (u8, u8) {
+    let ranges = [
+        (b'0', b'9', 0), (b'A', b'H', 10), (b'I', b'I', 1), (b'J', b'K', 18), (b'L', b'L', 1),
+        (b'M', b'N', 20), (b'O', b'O', 0), (b'P', b'T', 22), (b'V', b'Z', 27),
+    ];
+    ct_value(c, &ranges)
+}
+
+/// Goes through every range, so that the time taken doesn't depend on which one `c` is in
+fn ct_value(c: u8, ranges: &[(u8, u8, u8)]) -> (u8, u8) {
+    let mut value = 0;
+    let mut valid = 0;
+    for &(lo, hi, first) in ranges {
+        let mask = ct_in_range(c, lo, hi);
+        value |= mask & c.wrapping_sub(lo).wrapping_add(first);
+        valid |= mask;
+    }
+    (value, valid)
+}
+
+/// Value of an uppercase check symbol, and `0xFF` if it has one
+fn crockford_check_value(c: u8) -> (u8, u8) {
+    let (value, valid) = crockford_value(c);
+    let ranges = [(b'*', b'*', 32), (b'~', b'~', 33), (b'$', b'$', 34), (b'=', b'=', 35), (b'U', b'U', 36)];
+    let (extra, extra_valid) = ct_value(c, &ranges);
+    (value | extra, valid | extra_valid)
+}
+
+/// Value of the Crockford symbols modulo 37, and `0xFF` if they all have a value
+fn crockford_checksum(symbols: &[u8]) -> (u8, u8) {
+    let mut checksum = 0u16;
+    let mut valid = 0xFF;
+    for &c in symbols {
+        let (v, ok) = crockford_value(ct_to_upper(c));
+        valid &= ok;
+        checksum = (checksum*32 + v as u16) % 37;
+    }
+    (checksum as u8, valid)
+}
+
+/// `0xFF` if `check` is the check symbol of `body`
+fn crockford_check_matches(body: &[u8], check: u8) -> u8 {
+    let (checksum, valid) = crockford_checksum(body);
+    let (value, check_valid) = crockford_check_value(ct_to_upper(check));
+    valid & check_valid & ct_in_range(value, checksum, checksum)
+}
+
 #[cfg(test)]
 #[allow(dead_code, unused_attributes)]
 mod test {
-    use super::{encode, decode};
-    use super::Alphabet::{Crockford, RFC4648};
+    use super::{encode, decode, try_decode, DecodeError, encode_into, decode_into, encoded_len};
+    use super::Alphabet;
+    use super::Alphabet::{Crockford, CrockfordCheck, RFC4648, RFC4648Hex, ZBase32};
+    use super::Mode::{Lenient, Strict};
     use quickcheck;
     use std;
+    use std::prelude::v1::*;
     use rand::Rng;
 
+    const ALPHABETS: [Alphabet; 7] = [
+        Crockford, CrockfordCheck, RFC4648 { padding: true }, RFC4648 { padding: false },
+        RFC4648Hex { padding: true }, RFC4648Hex { padding: false }, ZBase32,
+    ];
+
     #[derive(Clone)]
     struct B32 {
         c: u8
@@ -130,6 +546,27 @@ mod test {
         }
     }
 
+    /// Both alphabets in both cases, padding, aliases and a few characters neither has
+    #[derive(Clone)]
+    struct Messy {
+        c: char
+    }
+
+    impl quickcheck::Arbitrary for Messy {
+        fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Messy {
+            let chars: Vec<char> = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz======*~$,é".chars().collect();
+            Messy {
+                c: chars[g.gen_range(0, chars.len())]
+            }
+        }
+    }
+
+    impl std::fmt::Debug for Messy {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+            self.c.fmt(f)
+        }
+    }
+
     #[test]
     fn masks_crockford() {
         assert_eq!(encode(Crockford, &[0xF8, 0x3E, 0x0F, 0x83, 0xE0]), "Z0Z0Z0Z0");
@@ -209,6 +646,206 @@ mod test {
         assert_eq!(decode(Crockford, "IiLlOo"), decode(Crockford, "111100"));
     }
 
+    #[test]
+    fn lenient_matches_decode() {
+        fn test(data: Vec<Messy>) -> bool {
+            let data: String = data.iter().map(|e| e.c).collect();
+            ALPHABETS.iter().all(|&alphabet| {
+                decode(alphabet, &data) == try_decode(alphabet, &data, Lenient).ok()
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<Messy>) -> bool)
+    }
+
+    #[test]
+    fn invertible_strict() {
+        fn test(data: Vec<u8>) -> bool {
+            ALPHABETS.iter().all(|&alphabet| {
+                try_decode(alphabet, &encode(alphabet, data.as_ref()), Strict) == Ok(data.clone())
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<u8>) -> bool)
+    }
+
+    #[test]
+    fn strict_is_canonical() {
+        fn test(data: Vec<Messy>) -> bool {
+            let data: String = data.iter().map(|e| e.c).collect();
+            ALPHABETS.iter().all(|&alphabet| {
+                match try_decode(alphabet, &data, Strict) {
+                    Ok(bytes) => encode(alphabet, &bytes) == data,
+                    Err(_) => true,
+                }
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<Messy>) -> bool)
+    }
+
+    #[test]
+    fn strict_errors() {
+        let padded = RFC4648 { padding: true };
+        let unpadded = RFC4648 { padding: false };
+        assert_eq!(try_decode(padded, "AE======", Strict), Ok(vec![0x01]));
+        assert_eq!(try_decode(unpadded, "AE", Strict), Ok(vec![0x01]));
+        assert_eq!(try_decode(padded, "ae======", Strict), Err(DecodeError::InvalidCharacter { position: 0, byte: b'a' }));
+        assert_eq!(try_decode(Crockford, "0Z0I", Strict), Err(DecodeError::InvalidCharacter { position: 3, byte: b'I' }));
+        assert_eq!(try_decode(padded, "AE====", Strict), Err(DecodeError::InvalidPadding { position: 6 }));
+        assert_eq!(try_decode(padded, "AE=======", Strict), Err(DecodeError::InvalidPadding { position: 8 }));
+        assert_eq!(try_decode(padded, "AE", Strict), Err(DecodeError::InvalidPadding { position: 2 }));
+        assert_eq!(try_decode(padded, "A=E=====", Strict), Err(DecodeError::InvalidPadding { position: 1 }));
+        assert_eq!(try_decode(unpadded, "AE======", Strict), Err(DecodeError::InvalidPadding { position: 2 }));
+        assert_eq!(try_decode(Crockford, "04=", Strict), Err(DecodeError::InvalidPadding { position: 2 }));
+        assert_eq!(try_decode(unpadded, "AEA", Strict), Err(DecodeError::InvalidLength { length: 3 }));
+        assert_eq!(try_decode(padded, "AEA=====", Strict), Err(DecodeError::InvalidLength { length: 3 }));
+        assert_eq!(try_decode(unpadded, "AB", Strict), Err(DecodeError::TrailingBits { position: 1 }));
+        assert_eq!(try_decode(Crockford, "05", Strict), Err(DecodeError::TrailingBits { position: 1 }));
+    }
+
+    #[test]
+    fn lenient_errors() {
+        assert_eq!(try_decode(RFC4648 { padding: false }, "ab======", Lenient), Ok(vec![0x00]));
+        assert_eq!(try_decode(RFC4648 { padding: true }, "A=E", Lenient), Ok(vec![0x00]));
+        assert_eq!(try_decode(Crockford, "0z0i", Lenient), Ok(vec![0x07, 0xC0]));
+        assert_eq!(try_decode(RFC4648 { padding: true }, "AB,CD", Lenient), Err(DecodeError::InvalidCharacter { position: 2, byte: b',' }));
+        assert_eq!(try_decode(Crockford, "AB=", Lenient), Err(DecodeError::InvalidPadding { position: 2 }));
+        assert_eq!(try_decode(RFC4648 { padding: false }, "ABé", Lenient), Err(DecodeError::InvalidCharacter { position: 2, byte: 0xC3 }));
+    }
+
+    #[test]
+    fn decode_error_display() {
+        assert_eq!(DecodeError::InvalidCharacter { position: 2, byte: b',' }.to_string(), "invalid character ',' at position 2");
+        assert_eq!(DecodeError::InvalidCharacter { position: 2, byte: 0xC3 }.to_string(), "invalid byte 0xc3 at position 2");
+        assert_eq!(DecodeError::InvalidPadding { position: 6 }.to_string(), "invalid padding at position 6");
+        assert_eq!(DecodeError::InvalidLength { length: 3 }.to_string(), "invalid length, nothing encodes to 3 characters");
+        assert_eq!(DecodeError::TrailingBits { position: 1 }.to_string(), "non-zero trailing bits in the character at position 1");
+    }
+
+    #[test]
+    fn encode_into_matches_encode() {
+        fn test(data: Vec<u8>) -> bool {
+            ALPHABETS.iter().all(|&alphabet| {
+                let encoded = encode(alphabet, &data);
+                let mut out = vec![0; encoded_len(alphabet, data.len())];
+                encode_into(alphabet, &data, &mut out) == Some(encoded.len()) && out == encoded.as_bytes()
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<u8>) -> bool)
+    }
+
+    #[test]
+    fn decode_into_matches_decode() {
+        fn test(data: Vec<Messy>) -> bool {
+            let data: String = data.iter().map(|e| e.c).collect();
+            ALPHABETS.iter().all(|&alphabet| {
+                let mut out = vec![0xAA; data.len()*5/8];
+                match (decode(alphabet, &data), decode_into(alphabet, &data, &mut out)) {
+                    (Some(decoded), Some(len)) => out[..len] == decoded[..],
+                    (None, None) => out.iter().all(|&b| b == 0 || b == 0xAA),
+                    _ => false,
+                }
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<Messy>) -> bool)
+    }
+
+    #[test]
+    fn decode_into_round_trips() {
+        fn test(data: Vec<u8>) -> bool {
+            ALPHABETS.iter().all(|&alphabet| {
+                let mut encoded = vec![0; encoded_len(alphabet, data.len())];
+                encode_into(alphabet, &data, &mut encoded).unwrap();
+                let mut out = vec![0; data.len()];
+                decode_into(alphabet, std::str::from_utf8(&encoded).unwrap(), &mut out) == Some(data.len()) && out == data
+            })
+        }
+        quickcheck::quickcheck(test as fn(Vec<u8>) -> bool)
+    }
+
+    #[test]
+    fn into_short_buffers() {
+        let mut out = [0u8; 7];
+        assert_eq!(encode_into(RFC4648 { padding: true }, &[0xF8, 0x3E, 0x7F, 0x83], &mut out), None);
+        assert_eq!(encode_into(RFC4648 { padding: false }, &[0xF8, 0x3E, 0x7F, 0x83], &mut out), Some(7));
+        assert_eq!(&out, b"7A7H7AY");
+        let mut out = [0u8; 4];
+        assert_eq!(decode_into(RFC4648 { padding: true }, "7A7H7A7H", &mut out), None);
+        assert_eq!(decode_into(RFC4648 { padding: true }, "7A7H7AY=", &mut out), Some(4));
+        assert_eq!(out, [0xF8, 0x3E, 0x7F, 0x83]);
+        assert_eq!(decode_into(RFC4648 { padding: true }, "7A7H,AY=", &mut out), None);
+        assert_eq!(out, [0; 4]);
+    }
+
+    #[test]
+    fn rfc4648_hex_vectors() {
+        let vectors = [
+            ("", ""), ("f", "CO======"), ("fo", "CPNG===="), ("foo", "CPNMU==="),
+            ("foob", "CPNMUOG="), ("fooba", "CPNMUOJ1"), ("foobar", "CPNMUOJ1E8======"),
+        ];
+        for &(data, encoded) in vectors.iter() {
+            assert_eq!(encode(RFC4648Hex { padding: true }, data.as_bytes()), encoded);
+            assert_eq!(encode(RFC4648Hex { padding: false }, data.as_bytes()), encoded.trim_end_matches('='));
+            assert_eq!(decode(RFC4648Hex { padding: true }, encoded).unwrap(), data.as_bytes());
+            assert_eq!(try_decode(RFC4648Hex { padding: true }, encoded, Strict).unwrap(), data.as_bytes());
+            assert_eq!(decode(RFC4648Hex { padding: false }, &encoded.to_ascii_lowercase()).unwrap(), data.as_bytes());
+        }
+        assert_eq!(try_decode(RFC4648Hex { padding: false }, "CPW", Strict), Err(DecodeError::InvalidCharacter { position: 2, byte: b'W' }));
+    }
+
+    #[test]
+    fn zbase32_vectors() {
+        assert_eq!(encode(ZBase32, &[0xF0, 0xBF, 0xC7]), "6n9hq");
+        assert_eq!(encode(ZBase32, &[0xD4, 0x7A, 0x04]), "4t7ye");
+        assert_eq!(encode(ZBase32, b"foobar"), "c3zs6aubqe");
+        assert_eq!(decode(ZBase32, "c3zs6aubqe").unwrap(), b"foobar");
+        assert_eq!(decode(ZBase32, "C3ZS6AUBQE").unwrap(), b"foobar");
+        assert_eq!(try_decode(ZBase32, "C3ZS6AUBQE", Strict), Err(DecodeError::InvalidCharacter { position: 0, byte: b'C' }));
+        assert_eq!(try_decode(ZBase32, "c3zs6aubqe==", Strict), Err(DecodeError::InvalidPadding { position: 10 }));
+        assert_eq!(decode(ZBase32, "c3zs6aubqv"), None);
+    }
+
+    #[test]
+    fn crockford_check_vectors() {
+        let vectors: [(&[u8], &str); 8] = [
+            (b"", "0"), (&[0x01], "044"), (&[0xFF], "ZWN"), (b"foobar", "CSQPYRK1E8R"),
+            (&[0x08], "10*"), (&[0x1B], "3C$"), (&[0x24], "4G~"), (&[0x12], "28="),
+        ];
+        for &(data, encoded) in vectors.iter() {
+            assert_eq!(encode(CrockfordCheck, data), encoded);
+            assert_eq!(decode(CrockfordCheck, encoded).unwrap(), data);
+            assert_eq!(try_decode(CrockfordCheck, encoded, Strict).unwrap(), data);
+            let mut out = [0; 8];
+            assert_eq!(decode_into(CrockfordCheck, encoded, &mut out), Some(data.len()));
+        }
+        assert_eq!(decode(CrockfordCheck, "14U"), Some(vec![0x09]));
+        assert_eq!(decode(CrockfordCheck, "14u"), Some(vec![0x09]));
+        assert_eq!(decode(CrockfordCheck, "csqpyrkle8r"), Some(b"foobar".to_vec()));
+        assert_eq!(try_decode(CrockfordCheck, "14u", Strict), Err(DecodeError::InvalidCharacter { position: 2, byte: b'u' }));
+        assert_eq!(decode(CrockfordCheck, ""), None);
+        assert_eq!(try_decode(CrockfordCheck, "", Lenient), Err(DecodeError::InvalidLength { length: 0 }));
+        assert_eq!(try_decode(CrockfordCheck, "ZW#", Lenient), Err(DecodeError::InvalidCharacter { position: 2, byte: b'#' }));
+        assert_eq!(try_decode(CrockfordCheck, "ZWé", Lenient), Err(DecodeError::InvalidCharacter { position: 2, byte: 0xC3 }));
+        assert_eq!(DecodeError::CheckSymbol { position: 2 }.to_string(), "check symbol at position 2 doesn't match");
+    }
+
+    #[test]
+    fn crockford_check_catches_typos() {
+        fn test(data: Vec<u8>, position: usize, symbol: u8) -> bool {
+            let mut encoded = encode(CrockfordCheck, &data).into_bytes();
+            let position = position % encoded.len();
+            let symbol = super::CROCKFORD_ALPHABET[symbol as usize % 32];
+            if encoded[position] == symbol || position == encoded.len() - 1 {
+                return true;
+            }
+            encoded[position] = symbol;
+            let encoded = std::string::String::from_utf8(encoded).unwrap();
+            let mut out = vec![0; data.len() + 1];
+            decode(CrockfordCheck, &encoded).is_none()
+                && try_decode(CrockfordCheck, &encoded, Lenient) == Err(DecodeError::CheckSymbol { position: encoded.len() - 1 })
+                && decode_into(CrockfordCheck, &encoded, &mut out).is_none()
+        }
+        quickcheck::quickcheck(test as fn(Vec<u8>, usize, u8) -> bool)
+    }
+
     #[test]
     fn invalid_chars_crockford() {
         assert_eq!(decode(Crockford, ","), None)
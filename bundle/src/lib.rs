@@ -0,0 +1,309 @@
+//! Provisioning bundles dropped on the ESP by `totp-provision` and ingested by
+//! `totp-uefi` on the next boot.
+//!
+//! A bundle replaces the stored secret and digit count. It is encrypted and
+//! authenticated with keys derived from the secret it replaces, so only someone
+//! who already holds the current secret can rotate it, and the new secret never
+//! sits on the ESP in the clear.
+//!
+//! ```text
+//! magic "TOTPBNDL" | version | nonce[16] | E(digits | len | base32 secret) | tag[32]
+//! ```
+//!
+//! `E` is HMAC-SHA256 in counter mode over the nonce, the tag is HMAC-SHA256 over
+//! everything before it.
+#![no_std]
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const MAGIC: &[u8; 8] = b"TOTPBNDL";
+pub const VERSION: u8 = 1;
+pub const NONCE_LEN: usize = 16;
+pub const TAG_LEN: usize = 32;
+/// Location of the bundle on the ESP, one path component per element.
+pub const ESP_PATH: [&str; 3] = ["EFI", "totp-uefi", "provision.bin"];
+/// Longest base32 secret a bundle can carry, matching the app's key buffer.
+pub const MAX_SECRET: usize = 60;
+
+const HEADER_LEN: usize = MAGIC.len() + 1 + NONCE_LEN;
+/// Size of the largest possible bundle, useful for sizing read buffers.
+pub const MAX_LEN: usize = HEADER_LEN + 2 + MAX_SECRET + TAG_LEN;
+
+const BASE32_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleError {
+    /// The bundle is truncated, too long or not a bundle at all.
+    Malformed,
+    /// The bundle was written by a newer format version.
+    UnsupportedVersion(u8),
+    /// The tag does not match, i.e. it was sealed with another secret or tampered with.
+    BadTag,
+    /// The decrypted contents are not a usable secret and digit count.
+    InvalidPayload,
+    /// There is no current secret to verify the bundle against.
+    NoCurrentSecret,
+}
+
+impl core::fmt::Display for BundleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BundleError::Malformed => write!(f, "not a provisioning bundle"),
+            BundleError::UnsupportedVersion(v) => write!(f, "unsupported bundle version {}", v),
+            BundleError::BadTag => write!(f, "bundle was not sealed with the current secret"),
+            BundleError::InvalidPayload => write!(f, "bundle contains an invalid secret or digit count"),
+            BundleError::NoCurrentSecret => write!(f, "no secret is stored to verify the bundle with"),
+        }
+    }
+}
+
+/// Secret and digit count carried by a bundle; wiped on drop.
+pub struct Payload {
+    digits: u8,
+    secret: [u8; MAX_SECRET],
+    len: usize,
+}
+
+impl Payload {
+    /// Returns `None` unless `secret` is upper-case base32 of at most
+    /// [`MAX_SECRET`] characters and `digits` is 6, 7 or 8.
+    pub fn new(secret: &[u8], digits: u8) -> Option<Payload> {
+        if secret.is_empty() || secret.len() > MAX_SECRET {
+            return None;
+        }
+        if !secret.iter().all(|b| BASE32_CHARS.contains(b)) || !(6..=8).contains(&digits) {
+            return None;
+        }
+
+        let mut this = Payload { digits, secret: [0; MAX_SECRET], len: secret.len() };
+        this.secret[..secret.len()].copy_from_slice(secret);
+        Some(this)
+    }
+
+    pub fn digits(&self) -> u8 {
+        self.digits
+    }
+
+    /// The base32 encoded secret.
+    pub fn secret(&self) -> &[u8] {
+        &self.secret[..self.len]
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.len
+    }
+}
+
+impl Zeroize for Payload {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+        self.digits = 0;
+        self.len = 0;
+    }
+}
+
+impl Drop for Payload {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Payload {}
+
+/// Encryption and authentication keys derived from the raw (decoded) current secret.
+struct Keys {
+    enc: [u8; 32],
+    mac: [u8; 32],
+}
+
+impl Keys {
+    fn derive(current_secret: &[u8]) -> Keys {
+        Keys {
+            enc: prf(current_secret, &[b"totp-uefi bundle encryption"]),
+            mac: prf(current_secret, &[b"totp-uefi bundle authentication"]),
+        }
+    }
+
+    fn tag(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.mac).unwrap();
+        mac.update(data);
+        mac
+    }
+
+    fn apply_keystream(&self, nonce: &[u8], data: &mut [u8]) {
+        for (counter, chunk) in data.chunks_mut(32).enumerate() {
+            let mut block = prf(&self.enc, &[nonce, &(counter as u32).to_be_bytes()]);
+            for (b, k) in chunk.iter_mut().zip(block.iter()) {
+                *b ^= k;
+            }
+            block.zeroize();
+        }
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.enc.zeroize();
+        self.mac.zeroize();
+    }
+}
+
+fn prf(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(key).unwrap();
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Length of the bundle produced by [`seal`] for `payload`.
+pub fn sealed_len(payload: &Payload) -> usize {
+    HEADER_LEN + payload.encoded_len() + TAG_LEN
+}
+
+/// Writes the bundle for `payload` into `out`, returning its length.
+///
+/// `current_secret` is the raw secret currently stored on the device and `nonce`
+/// must be freshly random for every bundle. Returns `None` if `out` is shorter
+/// than [`sealed_len`] or `current_secret` is empty, as [`open`] rejects those.
+pub fn seal(current_secret: &[u8], nonce: &[u8; NONCE_LEN], payload: &Payload, out: &mut [u8]) -> Option<usize> {
+    let len = sealed_len(payload);
+    if out.len() < len || current_secret.is_empty() {
+        return None;
+    }
+    let keys = Keys::derive(current_secret);
+
+    out[..MAGIC.len()].copy_from_slice(MAGIC);
+    out[MAGIC.len()] = VERSION;
+    out[MAGIC.len() + 1..HEADER_LEN].copy_from_slice(nonce);
+
+    let body = &mut out[HEADER_LEN..len - TAG_LEN];
+    body[0] = payload.digits;
+    body[1] = payload.len as u8;
+    body[2..].copy_from_slice(payload.secret());
+    keys.apply_keystream(nonce, body);
+
+    let tag = keys.tag(&out[..len - TAG_LEN]).finalize().into_bytes();
+    out[len - TAG_LEN..len].copy_from_slice(&tag);
+    Some(len)
+}
+
+/// Verifies and decrypts a bundle sealed for `current_secret`.
+///
+/// The tag is checked in constant time before anything is decrypted. An empty
+/// `current_secret`, as on a device never provisioned, is refused: HMAC accepts
+/// an empty key, so anyone could seal a bundle for it.
+pub fn open(current_secret: &[u8], bundle: &[u8]) -> Result<Payload, BundleError> {
+    if current_secret.is_empty() {
+        return Err(BundleError::NoCurrentSecret);
+    }
+    if bundle.len() < HEADER_LEN + 2 + TAG_LEN || bundle.len() > MAX_LEN || &bundle[..MAGIC.len()] != MAGIC {
+        return Err(BundleError::Malformed);
+    }
+    if bundle[MAGIC.len()] != VERSION {
+        return Err(BundleError::UnsupportedVersion(bundle[MAGIC.len()]));
+    }
+
+    let keys = Keys::derive(current_secret);
+    let (signed, tag) = bundle.split_at(bundle.len() - TAG_LEN);
+    keys.tag(signed).verify_slice(tag).map_err(|_| BundleError::BadTag)?;
+
+    let nonce = &signed[MAGIC.len() + 1..HEADER_LEN];
+    let mut body = [0; 2 + MAX_SECRET];
+    let body_len = signed.len() - HEADER_LEN;
+    body[..body_len].copy_from_slice(&signed[HEADER_LEN..]);
+    keys.apply_keystream(nonce, &mut body[..body_len]);
+
+    let payload = if body[1] as usize == body_len - 2 {
+        Payload::new(&body[2..body_len], body[0])
+    } else {
+        None
+    };
+    body.zeroize();
+    payload.ok_or(BundleError::InvalidPayload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT: &[u8] = b"12345678901234567890";
+    const NONCE: [u8; NONCE_LEN] = [7; NONCE_LEN];
+
+    fn sealed(payload: &Payload) -> ([u8; MAX_LEN], usize) {
+        let mut out = [0; MAX_LEN];
+        let len = seal(CURRENT, &NONCE, payload, &mut out).unwrap();
+        (out, len)
+    }
+
+    #[test]
+    fn round_trip() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ", 8).unwrap();
+        let (out, len) = sealed(&payload);
+        assert_eq!(len, sealed_len(&payload));
+
+        let opened = open(CURRENT, &out[..len]).unwrap();
+        assert_eq!(opened.secret(), payload.secret());
+        assert_eq!(opened.digits(), 8);
+    }
+
+    #[test]
+    fn secret_is_not_stored_in_clear() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKRSXG5CTMVRXEZLU", 6).unwrap();
+        let (out, len) = sealed(&payload);
+        assert!(!out[..len].windows(8).any(|w| w == b"KRSXG5CT"));
+    }
+
+    #[test]
+    fn rejects_other_secret() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKRSXG5CTMVRXEZLU", 6).unwrap();
+        let (out, len) = sealed(&payload);
+        assert_eq!(open(b"09876543210987654321", &out[..len]).err(), Some(BundleError::BadTag));
+    }
+
+    #[test]
+    fn rejects_empty_current_secret() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKRSXG5CTMVRXEZLU", 6).unwrap();
+        let mut out = [0; MAX_LEN];
+        assert_eq!(seal(b"", &NONCE, &payload, &mut out), None);
+
+        let (out, len) = sealed(&payload);
+        assert_eq!(open(b"", &out[..len]).err(), Some(BundleError::NoCurrentSecret));
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKRSXG5CTMVRXEZLU", 6).unwrap();
+        let (out, len) = sealed(&payload);
+        for i in MAGIC.len() + 1..len {
+            let mut copy = out;
+            copy[i] ^= 1;
+            assert_eq!(open(CURRENT, &copy[..len]).err(), Some(BundleError::BadTag));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        let payload = Payload::new(b"KRSXG5CTMVRXEZLUKRSXG5CTMVRXEZLU", 6).unwrap();
+        let (mut out, len) = sealed(&payload);
+        assert_eq!(open(CURRENT, &out[..HEADER_LEN]).err(), Some(BundleError::Malformed));
+        assert_eq!(open(CURRENT, &[0; MAX_LEN + 1]).err(), Some(BundleError::Malformed));
+        out[MAGIC.len()] = 2;
+        assert_eq!(open(CURRENT, &out[..len]).err(), Some(BundleError::UnsupportedVersion(2)));
+    }
+
+    #[test]
+    fn payload_validation() {
+        assert!(Payload::new(b"", 6).is_none());
+        assert!(Payload::new(b"krsxg5ct", 6).is_none());
+        assert!(Payload::new(b"KRSXG5CT", 5).is_none());
+        assert!(Payload::new(b"KRSXG5CT", 9).is_none());
+        assert!(Payload::new(&[b'A'; MAX_SECRET + 1], 6).is_none());
+        assert!(Payload::new(&[b'A'; MAX_SECRET], 7).is_some());
+    }
+}
@@ -0,0 +1,229 @@
+//! Companion for totp-uefi: seals a new secret into a provisioning bundle and
+//! drops it on the ESP, where the firmware app picks it up on the next boot.
+//!
+//! The bundle is keyed by the secret currently stored in firmware, so the
+//! current secret has to be supplied as well.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rand::RngCore;
+use totp_bundle::Payload;
+use totp_rs::{Algorithm, Secret, TOTP};
+use zeroize::Zeroizing;
+
+const USAGE: &str = "\
+Usage: totp-provision --current FILE (--secret FILE | --generate 160|256) [--digits 6|7|8] [--esp DIR]
+
+Writes a provisioning bundle for totp-uefi to the ESP.
+
+  --current FILE    base32 secret currently stored in firmware ('-' for stdin)
+  --secret FILE     base32 secret to provision ('-' for stdin)
+  --generate BITS   generate a new 160 or 256-bit secret and print it
+  --digits N        code length, defaults to 6
+  --esp DIR         ESP mount point, defaults to /boot/efi";
+
+enum NewSecret {
+    File(PathBuf),
+    Generate(usize),
+}
+
+struct Options {
+    current: PathBuf,
+    secret: NewSecret,
+    digits: usize,
+    esp: PathBuf,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut current = None;
+    let mut secret = None;
+    let mut digits = 6;
+    let mut esp = PathBuf::from("/boot/efi");
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--current" => current = Some(PathBuf::from(value()?)),
+            "--secret" => secret = Some(NewSecret::File(PathBuf::from(value()?))),
+            "--generate" => {
+                secret = match value()?.as_str() {
+                    "160" => Some(NewSecret::Generate(20)),
+                    "256" => Some(NewSecret::Generate(32)),
+                    bits => return Err(format!("cannot generate a {}-bit secret", bits)),
+                }
+            }
+            "--digits" => digits = value()?.parse().map_err(|_| "--digits needs a number".to_string())?,
+            "--esp" => esp = PathBuf::from(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Options {
+        current: current.ok_or_else(|| format!("--current is required\n\n{}", USAGE))?,
+        secret: secret.ok_or_else(|| format!("--secret or --generate is required\n\n{}", USAGE))?,
+        digits,
+        esp,
+    })
+}
+
+/// Reads a base32 secret, dropping whitespace, dashes and padding as shown by
+/// the firmware app or authenticator exports.
+fn read_secret(path: &Path) -> Result<Zeroizing<String>, String> {
+    let mut raw = Zeroizing::new(String::new());
+    let read = if path == Path::new("-") {
+        io::stdin().read_to_string(&mut raw)
+    } else {
+        fs::File::open(path).and_then(|mut f| f.read_to_string(&mut raw))
+    };
+    read.map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+
+    Ok(Zeroizing::new(
+        raw.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+            .map(|c| c.to_ascii_uppercase())
+            .collect(),
+    ))
+}
+
+fn decode(encoded: &str) -> Result<Vec<u8>, String> {
+    Secret::Encoded(encoded.into()).to_bytes().map_err(|e| e.to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let current = read_secret(&options.current)?;
+    let current = Zeroizing::new(decode(&current).map_err(|e| format!("current secret: {}", e))?);
+    if current.is_empty() {
+        return Err("current secret is empty: a device without a secret accepts no bundle, \
+                    type or generate the first secret on it".to_string());
+    }
+
+    let (encoded, generated) = match &options.secret {
+        NewSecret::File(path) => (read_secret(path)?, false),
+        NewSecret::Generate(bytes) => {
+            let secret = Secret::generate_secret_sized(&mut rand::thread_rng(), *bytes).to_encoded();
+            (Zeroizing::new(secret.reveal().to_string()), true)
+        }
+    };
+
+    // same parameters as the firmware app, so TOTP::new rejects what it would reject
+    let totp = TOTP::new(Algorithm::SHA1, options.digits, 1, 30, decode(&encoded)?).map_err(|e| e.to_string())?;
+    let payload = Payload::new(encoded.as_bytes(), options.digits as u8)
+        .ok_or_else(|| format!("secret is longer than {} characters", totp_bundle::MAX_SECRET))?;
+
+    let mut nonce = [0; totp_bundle::NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut bundle = [0; totp_bundle::MAX_LEN];
+    let len = totp_bundle::seal(&current, &nonce, &payload, &mut bundle).unwrap();
+
+    let path = write_bundle(&options.esp, &bundle[..len])?;
+    println!("Wrote {}", path.display());
+    if generated {
+        println!("New secret: {}", encoded.as_str());
+    }
+    let code = totp.generate_current().map_err(|e| e.to_string())?;
+    println!("Current code: {} (valid for {} s)", code, totp.ttl().map_err(|e| e.to_string())?);
+    println!("The firmware app applies the bundle on the next boot.");
+    Ok(())
+}
+
+fn write_bundle(esp: &Path, bundle: &[u8]) -> Result<PathBuf, String> {
+    if !esp.is_dir() {
+        return Err(format!("{} is not a directory, is the ESP mounted?", esp.display()));
+    }
+
+    let [dirs @ .., file] = totp_bundle::ESP_PATH;
+    let dir: PathBuf = dirs.iter().fold(esp.to_path_buf(), |p, d| p.join(d));
+    let path = dir.join(file);
+    let tmp = dir.join(format!("{}.tmp", file));
+
+    // the firmware must never see a half-written bundle
+    let write = || -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(bundle)?;
+        f.sync_all()?;
+        fs::rename(&tmp, &path)
+    };
+    write().map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn main() -> ExitCode {
+    match parse_args(env::args().skip(1)).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory standing in for the ESP, removed when dropped.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let dir = env::temp_dir().join(format!("totp-provision-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Scratch(dir)
+        }
+
+        fn run(&self, current: &str, secret: &[&str]) -> Result<(), String> {
+            let path = self.0.join("current.txt");
+            fs::write(&path, current).unwrap();
+            let mut args = vec!["--current", path.to_str().unwrap(), "--esp", self.0.to_str().unwrap()];
+            args.extend_from_slice(secret);
+            run(parse_args(args.into_iter().map(String::from))?)
+        }
+
+        fn bundle(&self) -> Vec<u8> {
+            let path = totp_bundle::ESP_PATH.iter().fold(self.0.clone(), |p, d| p.join(d));
+            fs::read(path).unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn generates_a_secret() {
+        let esp = Scratch::new("generate");
+        esp.run("krsx g5ct mvrx ezlu kn2x azls knsw g4tf oq==\n", &["--generate", "160", "--digits", "8"]).unwrap();
+
+        let payload = totp_bundle::open(b"TestSecretSuperSecret", &esp.bundle()).unwrap();
+        assert_eq!(payload.digits(), 8);
+        let secret = decode(std::str::from_utf8(payload.secret()).unwrap()).unwrap();
+        assert_eq!(secret.len(), 20);
+    }
+
+    #[test]
+    fn seals_a_given_secret() {
+        let esp = Scratch::new("secret");
+        let secret = esp.0.join("secret.txt");
+        fs::write(&secret, "GEZD-GNBV-GY3T-QOJQ-GEZD-GNBV-GY3T-QOJQ").unwrap();
+        esp.run("KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ", &["--secret", secret.to_str().unwrap()]).unwrap();
+
+        let payload = totp_bundle::open(b"TestSecretSuperSecret", &esp.bundle()).unwrap();
+        assert_eq!(payload.secret(), b"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(payload.digits(), 6);
+    }
+
+    #[test]
+    fn needs_a_current_secret() {
+        let esp = Scratch::new("empty");
+        assert!(esp.run("\n", &["--generate", "160"]).unwrap_err().starts_with("current secret is empty"));
+        assert!(!esp.0.join("EFI").exists());
+    }
+}
@@ -0,0 +1,367 @@
+//! In-memory stand-in for the firmware services, so the totp-uefi screen can be
+//! run in a terminal and tested without booting QEMU.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{FixedOffset, TimeZone};
+use totp_uefi::app::TotpState;
+use totp_uefi::demo::DemoRng;
+use totp_uefi::platform::{Clock, Entropy, FrameLog, Key, KeySource, Now, Screen, VariableStore};
+
+/// Simulated firmware: a controllable clock, queued key presses, a captured
+/// screen and a variable store that lives as long as the `Sim`.
+pub struct Sim {
+    /// Unix time reported by the clock.
+    pub time: i64,
+    pub offset: FixedOffset,
+    pub offset_known: bool,
+    pub keys: VecDeque<Key>,
+    /// Columns of each text mode, cycled through by [`Screen::next_text_mode`].
+    pub modes: Vec<usize>,
+    pub mode: usize,
+    pub block_glyphs: bool,
+    pub variables: BTreeMap<String, Vec<u8>>,
+    /// Seed of the next generated secret; `None` makes secret generation fail.
+    pub seed: Option<u64>,
+    /// Frames dumped in demo mode, with their index.
+    pub log: Vec<(u64, String)>,
+    frame: String,
+}
+
+impl Sim {
+    pub fn new(time: i64) -> Sim {
+        Sim {
+            time,
+            offset: FixedOffset::east_opt(0).unwrap(),
+            offset_known: true,
+            keys: VecDeque::new(),
+            modes: vec![80, 100, 128],
+            mode: 0,
+            block_glyphs: true,
+            variables: BTreeMap::new(),
+            seed: Some(0),
+            log: Vec::new(),
+            frame: String::new(),
+        }
+    }
+
+    pub fn advance(&mut self, seconds: i64) {
+        self.time += seconds;
+    }
+
+    pub fn press(&mut self, key: Key) {
+        self.keys.push_back(key);
+    }
+
+    /// Queues every character of `text` as a key press.
+    pub fn type_str(&mut self, text: &str) {
+        self.keys.extend(text.chars().map(Key::Char));
+    }
+
+    /// Runs the main loop until every queued key has been handled, then redraws.
+    pub fn run(&mut self, totp: &mut TotpState) {
+        while !self.keys.is_empty() {
+            totp.step(self);
+        }
+        totp.draw(self);
+    }
+
+    /// Everything printed since the screen was last cleared.
+    pub fn frame(&self) -> &str {
+        &self.frame
+    }
+}
+
+impl Clock for Sim {
+    fn now(&mut self) -> Now {
+        let time = self.offset.timestamp_opt(self.time, 0).unwrap();
+        Now { time, offset_known: self.offset_known }
+    }
+}
+
+impl KeySource for Sim {
+    fn read_key(&mut self) -> Option<Key> {
+        self.keys.pop_front()
+    }
+}
+
+impl Screen for Sim {
+    fn clear(&mut self) {
+        self.frame.clear();
+    }
+
+    fn print(&mut self, s: &str) {
+        self.frame.push_str(s);
+    }
+
+    fn columns(&mut self) -> usize {
+        self.modes[self.mode]
+    }
+
+    fn block_glyphs_supported(&mut self) -> bool {
+        self.block_glyphs
+    }
+
+    fn next_text_mode(&mut self) -> bool {
+        if self.modes.len() < 2 {
+            return false;
+        }
+        self.mode = (self.mode + 1) % self.modes.len();
+        true
+    }
+}
+
+impl VariableStore for Sim {
+    fn get<'a>(&mut self, name: &str, buf: &'a mut [u8]) -> Option<&'a [u8]> {
+        let value = self.variables.get(name)?;
+        let buf = buf.get_mut(..value.len())?;
+        buf.copy_from_slice(value);
+        Some(buf)
+    }
+
+    fn set(&mut self, name: &str, data: &[u8]) {
+        self.variables.insert(name.to_string(), data.to_vec());
+    }
+}
+
+impl FrameLog for Sim {
+    fn log_frame(&mut self, index: u64, frame: &str) {
+        self.log.push((index, frame.to_string()));
+    }
+}
+
+impl Entropy for Sim {
+    type Rng<'a> = DemoRng;
+
+    fn rng(&mut self) -> Option<DemoRng> {
+        let seed = self.seed?;
+        self.seed = Some(seed.wrapping_add(1));
+        Some(DemoRng::new(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use totp_uefi::app;
+    use totp_uefi::demo::{Demo, DemoConfig, Script};
+    use totp_uefi::key::SecretKey;
+
+    /// The RFC 6238 SHA-1 seed, "12345678901234567890".
+    const RFC_SECRET: &[u8] = b"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn boot<P: Screen + VariableStore>(sim: &mut P) -> TotpState {
+        let secret = app::load_secret(sim);
+        let digits = app::load_digits(sim);
+        let block_glyphs = sim.block_glyphs_supported();
+        TotpState::new(secret, digits, block_glyphs)
+    }
+
+    fn provisioned(time: i64, digits: u8) -> Sim {
+        let mut sim = Sim::new(time);
+        sim.set("totp_key", RFC_SECRET);
+        sim.set("totp_digits", &[digits]);
+        sim
+    }
+
+    #[test]
+    fn draw_snapshot() {
+        let mut sim = provisioned(1234567890, 6);
+        let totp = boot(&mut sim);
+        totp.draw(&mut sim);
+        assert_eq!(sim.frame(), include_str!("snapshots/draw.txt"));
+    }
+
+    #[test]
+    fn draw_snapshot_with_preview() {
+        let mut sim = provisioned(1111111109, 8);
+        sim.offset_known = false;
+        let totp = boot(&mut sim);
+        totp.draw(&mut sim);
+        assert_eq!(sim.frame(), include_str!("snapshots/draw_preview.txt"));
+    }
+
+    #[test]
+    fn draw_snapshot_without_secret() {
+        let mut sim = Sim::new(0);
+        let totp = boot(&mut sim);
+        totp.draw(&mut sim);
+        assert_eq!(sim.frame(), include_str!("snapshots/draw_empty.txt"));
+    }
+
+    #[test]
+    fn typing_a_secret() {
+        let mut sim = Sim::new(1234567890);
+        let mut totp = boot(&mut sim);
+
+        sim.type_str("gezd gnbv=");
+        sim.press(Key::Backspace);
+        sim.type_str("vgy3tqojqgezdgnbvgy3tqojq");
+        sim.run(&mut totp);
+
+        assert_eq!(totp.secret.as_str(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(sim.variables["totp_key"], RFC_SECRET);
+        assert!(sim.frame().starts_with("Secret: GEZD-GNBV-GY3T-QOJQ-GEZD-GNBV-GY3T-QOJQ\r\n"));
+        assert!(!sim.frame().contains("null"));
+    }
+
+    #[test]
+    fn backspace_on_empty_secret_saves_nothing() {
+        let mut sim = Sim::new(0);
+        let mut totp = boot(&mut sim);
+        sim.press(Key::Backspace);
+        sim.press(Key::Other);
+        sim.run(&mut totp);
+        assert!(sim.variables.is_empty());
+    }
+
+    #[test]
+    fn cycling_digits() {
+        let mut sim = provisioned(1234567890, 6);
+        let mut totp = boot(&mut sim);
+
+        for digits in [7, 8, 6] {
+            sim.press(Key::Function(4));
+            sim.run(&mut totp);
+            assert_eq!(totp.digits, digits);
+            assert_eq!(sim.variables["totp_digits"], [digits as u8]);
+        }
+    }
+
+    #[test]
+    fn generating_secrets() {
+        let mut sim = Sim::new(1234567890);
+        let mut totp = boot(&mut sim);
+
+        sim.press(Key::Function(2));
+        sim.run(&mut totp);
+        let pending = totp.pending.as_ref().unwrap();
+        assert_eq!(pending.len(), 32);
+        assert!(totp.secret.is_empty());
+        assert!(sim.frame().contains("New secret: "));
+        assert!(sim.variables.is_empty());
+
+        sim.press(Key::Enter);
+        sim.run(&mut totp);
+        let first = SecretKey::from_bytes(&sim.variables["totp_key"]);
+        assert_eq!(first.len(), 32);
+        assert_eq!(totp.secret.as_str(), first.as_str());
+        assert!(totp.pending.is_none());
+
+        sim.press(Key::Function(3));
+        sim.press(Key::Enter);
+        sim.run(&mut totp);
+        assert_eq!(totp.secret.len(), 52);
+        assert_ne!(totp.secret.as_str(), first.as_str());
+
+        sim.seed = None;
+        sim.press(Key::Function(2));
+        sim.run(&mut totp);
+        assert!(totp.pending.is_none());
+        assert_eq!(totp.secret.len(), 52);
+    }
+
+    #[test]
+    fn generated_secret_needs_confirmation() {
+        let mut sim = provisioned(1234567890, 6);
+        let mut totp = boot(&mut sim);
+
+        // neither a stray key nor a cancelled secret touches the enrolled one
+        sim.press(Key::Function(3));
+        sim.type_str("a");
+        sim.press(Key::Backspace);
+        sim.press(Key::Function(4));
+        sim.run(&mut totp);
+        assert!(totp.pending.is_some());
+        assert_eq!(sim.variables["totp_key"], RFC_SECRET);
+        assert_eq!(sim.variables["totp_digits"], [6]);
+
+        sim.press(Key::Escape);
+        sim.run(&mut totp);
+        assert!(totp.pending.is_none());
+        assert_eq!(totp.secret.as_bytes(), RFC_SECRET);
+        assert_eq!(sim.variables["totp_key"], RFC_SECRET);
+        assert_eq!(sim.frame(), include_str!("snapshots/draw.txt"));
+    }
+
+    #[test]
+    fn switching_mode_and_font() {
+        let mut sim = provisioned(1234567890, 6);
+        let mut totp = boot(&mut sim);
+        sim.variables.clear();
+
+        sim.press(Key::Function(6));
+        sim.run(&mut totp);
+        assert!(sim.frame().contains('#') && !sim.frame().contains('█'));
+
+        // the new mode is probed again
+        sim.press(Key::Function(5));
+        sim.run(&mut totp);
+        assert_eq!(sim.mode, 1);
+        assert!(sim.frame().contains('█'));
+        // neither is persisted
+        assert!(sim.variables.is_empty());
+    }
+
+    #[test]
+    fn demo_replays_script_with_fixed_clock() {
+        let config = DemoConfig::parse("--time 1234567890 --rate 10 --dump").unwrap().unwrap();
+        let script = Script::parse("type gezdgnbvgy3tqojqgezdgnbvgy3tqojq\nwait 2\nkey F4\nquit").unwrap();
+        let mut demo = Demo::new(Sim::new(0), &config, Some(script));
+        let mut totp = boot(&mut demo);
+
+        while !demo.finished() {
+            totp.step(&mut demo);
+        }
+        demo.finish();
+
+        // 32 characters, 2 frames of waiting, F4 and quit, all one frame each
+        let log = &demo.platform().log;
+        assert_eq!(log.len(), 36);
+        assert_eq!(log[0], (0, "Secret: \r\nKey: null\r\n[F2]/[F3] new 160/256-bit secret  [F4] 6/7/8 digits  [F5] text mode  [F6] font".to_string()));
+        // the secret was complete in frame 32, drawn at 1234567890 + 32 * 10
+        assert!(log[32].1.contains("[2009-02-13 23:36:50 +00:00]"));
+        assert_eq!(log[35].0, 35);
+        assert!(log[35].1.contains("[2009-02-13 23:37:20 +00:00]"));
+        assert_eq!(demo.get("totp_digits", &mut [0]), Some(&[7][..]));
+        assert!(demo.platform().variables.is_empty());
+    }
+
+    #[test]
+    fn demo_logs_only_changed_frames() {
+        let config = DemoConfig::parse("--time 1234567890 --dump").unwrap().unwrap();
+        let script = Script::parse("wait 10\nquit").unwrap();
+        let mut demo = Demo::new(provisioned(0, 6), &config, Some(script));
+        let mut totp = boot(&mut demo);
+
+        while !demo.finished() {
+            totp.step(&mut demo);
+        }
+        demo.finish();
+
+        // the stored secret is not shown either
+        let log = &demo.platform().log;
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].1, include_str!("snapshots/draw_empty.txt"));
+    }
+
+    #[test]
+    fn demo_generates_reproducible_secrets() {
+        let run = || {
+            let config = DemoConfig::parse("--time 1234567890").unwrap().unwrap();
+            let script = Script::parse("key F2\nkey ENTER\nquit").unwrap();
+            let mut demo = Demo::new(provisioned(0, 6), &config, Some(script));
+            let mut totp = boot(&mut demo);
+            while !demo.finished() {
+                totp.step(&mut demo);
+            }
+            assert_eq!(demo.platform().variables["totp_key"], RFC_SECRET);
+            totp.secret
+        };
+
+        let secret = run();
+        assert_eq!(secret.as_str().len(), 32);
+        assert_ne!(secret.as_bytes(), RFC_SECRET);
+        assert_eq!(run().as_str(), secret.as_str());
+    }
+}
@@ -0,0 +1,108 @@
+//! Runs the totp-uefi screen in a terminal, with variables kept in memory.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+use rand_core::{OsRng, RngCore};
+use totp_uefi::app::{self, TotpState};
+use totp_uefi::platform::{Key, Screen};
+use totp_uefi_sim::Sim;
+
+const USAGE: &str = "\
+Usage: totp-uefi-sim [--time UNIX]
+
+Runs the app with an in-memory variable store. The clock starts at --time
+(default: now) and runs in real time. Keys go to the app, except:
+
+  PgUp/PgDn   move the clock 30 s forward/back
+  Ctrl-C      quit";
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<i64>, String> {
+    let mut time = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time" => {
+                let value = args.next().ok_or("--time needs a value")?;
+                time = Some(value.parse().map_err(|_| "--time needs a Unix timestamp")?);
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(time)
+}
+
+fn run(start_time: i64) -> io::Result<()> {
+    let (width, _) = terminal::size()?;
+    let mut sim = Sim::new(start_time);
+    sim.modes.retain(|&columns| columns <= width as usize);
+    if sim.modes.is_empty() {
+        sim.modes.push(width as usize);
+    }
+    sim.seed = Some(OsRng.next_u64());
+
+    let secret = app::load_secret(&mut sim);
+    let digits = app::load_digits(&mut sim);
+    let block_glyphs = sim.block_glyphs_supported();
+    let mut totp = TotpState::new(secret, digits, block_glyphs);
+
+    let mut out = io::stdout();
+    let started = Instant::now();
+    let mut shift = 0;
+    let mut shown = String::new();
+    loop {
+        sim.time = start_time + started.elapsed().as_secs() as i64 + shift;
+        totp.step(&mut sim);
+        if sim.frame() != shown {
+            shown = sim.frame().to_string();
+            execute!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+            out.write_all(shown.as_bytes())?;
+            out.flush()?;
+        }
+
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        if let Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. }) = event::read()? {
+            match code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::PageUp => shift += 30,
+                KeyCode::PageDown => shift -= 30,
+                KeyCode::Char(c) => sim.press(Key::Char(c)),
+                KeyCode::Backspace => sim.press(Key::Backspace),
+                KeyCode::Enter => sim.press(Key::Enter),
+                KeyCode::Esc => sim.press(Key::Escape),
+                KeyCode::F(n) => sim.press(Key::Function(n)),
+                _ => sim.press(Key::Other),
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let start_time = match parse_args(env::args().skip(1)) {
+        Ok(time) => time.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64),
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut out = io::stdout();
+    let result = terminal::enable_raw_mode()
+        .and_then(|_| execute!(out, terminal::EnterAlternateScreen))
+        .and_then(|_| run(start_time));
+    let _ = execute!(out, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use alloc::string::{ToString, String};
+use totp_rs::{Algorithm, TOTP, Secret, TimeSource};
+use zeroize::Zeroize;
+
+use crate::font::Layout;
+use crate::key::SecretKey;
+use crate::platform::{Clock, Entropy, Key, KeySource, Now, Platform, Screen, VariableStore};
+
+
+/// Seconds before a step boundary from which the upcoming code is shown as well.
+const PREVIEW_SECONDS: u64 = 5;
+
+const SPACES: &str = "                                                                ";
+fn print_spaces<S: Screen>(screen: &mut S, mut n: usize) {
+    while n > 0 {
+        let chunk = n.min(SPACES.len());
+        screen.print(&SPACES[..chunk]);
+        n -= chunk;
+    }
+}
+
+fn print_key<S: Screen>(screen: &mut S, key: &SecretKey, split: usize) {
+    if key.is_empty() {return;}
+
+    let key = key.as_str();
+    for i in 0..(key.len()-1)/split {
+        screen.print(&key[i*split..(i+1)*split]);
+        screen.print("-");
+    }
+    screen.print(&key[(key.len()-1)/split*split..]);
+}
+
+fn print_code<S: Screen>(screen: &mut S, token: &str, layout: &Layout) {
+    for row in 0..layout.font.height() {
+        print_spaces(screen, layout.indent);
+        for (i, c) in token.chars().enumerate() {
+            if layout.gap_before(i) { print_spaces(screen, layout.gap); }
+            screen.print(layout.font.digit_row(c, row));
+        }
+        screen.print("\r\n");
+    }
+}
+
+pub struct TotpState {
+    pub secret: SecretKey,
+    /// Generated secret waiting to be confirmed before it replaces `secret`.
+    pub pending: Option<SecretKey>,
+    pub digits: usize,
+    totp:   Option<TOTP>,
+    block_glyphs: bool,
+    /// One-off message shown above the help line, e.g. the outcome of provisioning.
+    pub notice: Option<String>,
+}
+impl TotpState {
+    pub fn new(secret: SecretKey, digits: usize, block_glyphs: bool) -> TotpState {
+        let mut this = TotpState {secret, pending: None, digits, totp: None, block_glyphs, notice: None};
+        this.update_totp();
+        this
+    }
+    
+    pub fn draw<P: Screen + Clock>(&self, platform: &mut P) {
+        platform.clear();
+        platform.print("Secret: ");
+        print_key(platform, &self.secret, 4);
+        platform.print("\r\nKey: ");
+        match &self.totp {
+            None    => { platform.print("null"); }
+            Some(t) => {
+                let now = self.get_time(platform);
+                let Ok(time) = now.now();
+                let mut buf = [0u8; 8];
+                let token = t.generate_into(time, &mut buf).unwrap_or("");
+                let columns = platform.columns();
+                let layout = Layout::choose(columns, token.len(), self.block_glyphs);
+    
+                platform.print("\n\n\r\n");
+                print_code(platform, token, &layout);
+    
+                let Ok(remaining) = t.ttl_with(&now);
+                if remaining <= PREVIEW_SECONDS {
+                    let next = t.generate_into(time + remaining, &mut buf).unwrap_or("");
+                    let preview = Layout::choose_smaller(columns, next.len(), self.block_glyphs, layout.font.width);
+    
+                    platform.print("\r\n");
+                    print_spaces(platform, preview.indent);
+                    platform.print("Next code in ");
+                    platform.print(&remaining.to_string());
+                    platform.print(" s:\r\n");
+                    print_code(platform, next, &preview);
+                }
+            }
+        }
+        if let Some(notice) = &self.notice {
+            platform.print("\r\n");
+            platform.print(notice);
+        }
+        match &self.pending {
+            Some(pending) => {
+                platform.print("\r\nNew secret: ");
+                print_key(platform, pending, 4);
+                platform.print("\r\n[Enter] replace the current secret with it  [Esc] discard it");
+            }
+            None => platform.print("\r\n[F2]/[F3] new 160/256-bit secret  [F4] 6/7/8 digits  [F5] text mode  [F6] font")
+        }
+    }
+    
+    fn get_time<P: Screen + Clock>(&self, platform: &mut P) -> Now {
+        let now = platform.now();
+    
+        platform.print(" [");
+        if !now.offset_known { platform.print("INACCURATE_OFFSET "); }
+        platform.print(&now.time.to_string());
+        platform.print("] ");
+        now
+    }
+    
+    pub fn update_secret<P: KeySource + Screen + Entropy>(&mut self, platform: &mut P) -> bool {
+        if let Some(key) = platform.read_key() {
+            // a generated secret only replaces the enrolled one once confirmed
+            if let Some(pending) = self.pending.take() {
+                match key {
+                    Key::Enter => { self.secret = pending; },
+                    Key::Escape => {return false;},
+                    _ => {
+                        self.pending = Some(pending);
+                        return false;
+                    }
+                }
+                self.update_totp();
+                return true;
+            }
+    
+            match key {
+                Key::Backspace => {
+                    if !self.secret.pop() {return false;}
+                },
+                Key::Char(c) => {
+                    if !self.secret.push(c) {return false;}
+                },
+                Key::Function(2) => {
+                    self.generate_secret(platform, 20);
+                    return false;
+                },
+                Key::Function(3) => {
+                    self.generate_secret(platform, 32);
+                    return false;
+                },
+                Key::Function(4) => {
+                    self.digits = if self.digits >= 8 {6} else {self.digits + 1};
+                },
+                Key::Function(5) => {
+                    if platform.next_text_mode() {
+                        self.block_glyphs = platform.block_glyphs_supported();
+                    }
+                    return false;
+                },
+                Key::Function(6) => {
+                    // the probe is not trusted by every console, e.g. with serial redirection
+                    self.block_glyphs = !self.block_glyphs;
+                    return false;
+                },
+                Key::Function(_) | Key::Enter | Key::Escape | Key::Other => {return false;}
+            }
+    
+            self.update_totp();
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// One iteration of the main loop: redraws, handles at most one key press and
+    /// saves the settings if it changed them.
+    pub fn step<P: Platform>(&mut self, platform: &mut P) {
+        self.draw(platform);
+        if self.update_secret(platform) {
+            save_secret(platform, &self.secret);
+            save_digits(platform, self.digits);
+        }
+    }
+    
+    /// Generates a secret of `bytes` random bytes and leaves it pending confirmation.
+    fn generate_secret<E: Entropy>(&mut self, entropy: &mut E, bytes: usize) {
+        let mut rng = match entropy.rng() {
+            Some(rng) => rng,
+            None => return
+        };
+    
+        // both the raw and the encoded secret are wiped when dropped
+        let secret = Secret::generate_secret_sized(&mut rng, bytes).to_encoded();
+        if let Secret::Encoded(s) = &secret {
+            self.pending = Some(SecretKey::from_bytes(s.expose_secret().as_bytes()));
+        }
+    }
+    
+    fn update_totp(&mut self) {
+        // the encoded copy is wiped on drop, the decoded bytes are owned by TOTP
+        let secret = Secret::Encoded(self.secret.as_str().into()).to_bytes();
+        self.totp = secret.ok().and_then(|s| {
+            TOTP::new(Algorithm::SHA1, self.digits, 1, 30, s).ok()
+        });
+    }
+}
+
+
+pub fn load_secret<V: VariableStore>(store: &mut V) -> SecretKey {
+    let mut buf = [0; SecretKey::CAPACITY];
+    let key = match store.get("totp_key", &mut buf) {
+        Some(var_bytes) => SecretKey::from_bytes(var_bytes),
+        None => SecretKey::new()
+    };
+    buf.zeroize();
+    key
+}
+
+pub fn save_secret<V: VariableStore>(store: &mut V, key: &SecretKey) {
+    store.set("totp_key", key.as_bytes());
+}
+
+pub fn load_digits<V: VariableStore>(store: &mut V) -> usize {
+    let mut buf = [0; 1];
+    match store.get("totp_digits", &mut buf) {
+        Some([digits @ 6..=8]) => *digits as usize,
+        _ => 6
+    }
+}
+
+pub fn save_digits<V: VariableStore>(store: &mut V, digits: usize) {
+    store.set("totp_digits", &[digits as u8]);
+}
@@ -0,0 +1,335 @@
+//! Reproducible runs for documentation screenshots and firmware regression tests.
+//!
+//! Demo mode is configured from the image's load options, e.g.
+//! `totp-uefi.efi --time 1234567890 --rate 1 --keys \EFI\totp-uefi\keys.txt --dump`,
+//! or from the same words in `\EFI\totp-uefi\demo.cfg`. It can pin the clock or
+//! advance it a fixed number of seconds per frame, replay scripted key presses
+//! and log every frame that differs from the previous one. Settings changed
+//! during a demo are kept in memory, so a demo never overwrites the stored secret.
+//! It never reads it either: a demo starts without a secret and generates new
+//! ones from a fixed seed, so neither the screen nor a dump reveals a real one.
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use chrono::{DateTime, FixedOffset, TimeZone};
+use core::fmt;
+use rand_core::{impls, CryptoRng, Error, RngCore};
+
+use crate::platform::{Clock, Entropy, FrameLog, Key, KeySource, Now, Screen, VariableStore};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DemoError {
+    /// An option is missing its value or the value does not parse.
+    InvalidOption(&'static str),
+    /// The key script file could not be read.
+    KeysUnreadable,
+    /// The key script has an unknown command on this (1-based) line.
+    InvalidScript(usize),
+}
+impl fmt::Display for DemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DemoError::InvalidOption(option) => write!(f, "invalid value for {}", option),
+            DemoError::KeysUnreadable => write!(f, "cannot read the key script"),
+            DemoError::InvalidScript(line) => write!(f, "key script line {} is invalid", line),
+        }
+    }
+}
+
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DemoConfig {
+    /// Unix time of the first frame; the real clock is used if unset.
+    pub time: Option<i64>,
+    /// Seconds the clock advances per frame, 0 pins it.
+    pub rate: i64,
+    /// ESP path of the key script.
+    pub keys: Option<String>,
+    pub dump: bool,
+}
+impl DemoConfig {
+    /// Picks the demo options out of a command line, ignoring other words such as
+    /// the image name some shells pass first. Returns `None` if there are none.
+    pub fn parse(options: &str) -> Result<Option<DemoConfig>, DemoError> {
+        let mut config = DemoConfig::default();
+        let mut found = false;
+        let mut words = options.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "--time" => {
+                    let time = words.next().and_then(|w| w.parse().ok());
+                    config.time = Some(time.ok_or(DemoError::InvalidOption("--time"))?);
+                },
+                "--rate" => {
+                    let rate = words.next().and_then(|w| w.parse().ok());
+                    config.rate = rate.ok_or(DemoError::InvalidOption("--rate"))?;
+                },
+                "--keys" => {
+                    let path = words.next().ok_or(DemoError::InvalidOption("--keys"))?;
+                    config.keys = Some(path.replace('/', "\\"));
+                },
+                "--dump" => config.dump = true,
+                _ => continue
+            }
+            found = true;
+        }
+        Ok(if found {Some(config)} else {None})
+    }
+}
+
+
+enum Action {
+    Press(Key),
+    Wait(u32),
+    Quit,
+}
+
+/// Scripted key presses, one per frame.
+///
+/// Each line is `type TEXT`, `key NAME` (`F1` to `F12`, `BS`, `ENTER`, `ESC`), `wait FRAMES` or
+/// `quit`; blank lines and lines starting with `#` are skipped.
+pub struct Script {
+    actions: VecDeque<Action>,
+    waiting: u32,
+    finished: bool,
+}
+impl Script {
+    pub fn parse(text: &str) -> Result<Script, DemoError> {
+        let mut actions = VecDeque::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {continue;}
+    
+            let (command, arg) = line.split_once(' ').map_or((line, ""), |(c, a)| (c, a.trim()));
+            match (command, arg) {
+                ("type", text) => actions.extend(text.chars().map(|c| Action::Press(Key::Char(c)))),
+                ("key", "BS") => actions.push_back(Action::Press(Key::Backspace)),
+                ("key", "ENTER") => actions.push_back(Action::Press(Key::Enter)),
+                ("key", "ESC") => actions.push_back(Action::Press(Key::Escape)),
+                ("key", name) => {
+                    let f = name.strip_prefix('F').and_then(|f| f.parse().ok()).filter(|f| (1..=12).contains(f));
+                    actions.push_back(Action::Press(Key::Function(f.ok_or(DemoError::InvalidScript(n + 1))?)));
+                },
+                ("wait", frames) => actions.push_back(Action::Wait(frames.parse().map_err(|_| DemoError::InvalidScript(n + 1))?)),
+                ("quit", "") => actions.push_back(Action::Quit),
+                _ => return Err(DemoError::InvalidScript(n + 1))
+            }
+        }
+        Ok(Script {actions, waiting: 0, finished: false})
+    }
+    
+    fn next_key(&mut self) -> Option<Key> {
+        if self.waiting > 0 {
+            self.waiting -= 1;
+            return None;
+        }
+        match self.actions.pop_front()? {
+            Action::Press(key) => Some(key),
+            Action::Wait(frames) => {
+                self.waiting = frames.saturating_sub(1);
+                None
+            },
+            Action::Quit => {
+                self.finished = true;
+                None
+            }
+        }
+    }
+}
+
+
+/// Wraps a platform to replace its clock and keyboard and to log frames.
+pub struct Demo<P> {
+    platform: P,
+    start: Option<DateTime<FixedOffset>>,
+    rate: i64,
+    script: Option<Script>,
+    dump: bool,
+    frames: u64,
+    frame: String,
+    logged: String,
+    variables: BTreeMap<String, Vec<u8>>,
+    seed: u64,
+}
+impl<P: Clock> Demo<P> {
+    pub fn new(mut platform: P, config: &DemoConfig, script: Option<Script>) -> Demo<P> {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let start = match config.time {
+            Some(time) => utc.timestamp_opt(time, 0).single(),
+            None if config.rate != 0 => Some(platform.now().time),
+            None => None
+        };
+        Demo {platform, start, rate: config.rate, script, dump: config.dump, frames: 0, frame: String::new(), logged: String::new(), variables: BTreeMap::new(), seed: 0}
+    }
+    
+    pub fn platform(&self) -> &P {
+        &self.platform
+    }
+    
+    /// True once the script has reached `quit`.
+    pub fn finished(&self) -> bool {
+        self.script.as_ref().is_some_and(|s| s.finished)
+    }
+}
+impl<P: FrameLog> Demo<P> {
+    /// Logs the frame still being drawn; call before leaving the main loop.
+    pub fn finish(&mut self) {
+        self.log_frame();
+    }
+    
+    fn log_frame(&mut self) {
+        if !self.dump || self.frames == 0 || self.frame == self.logged {return;}
+    
+        self.platform.log_frame(self.frames - 1, &self.frame);
+        self.logged = self.frame.clone();
+    }
+}
+impl<P: Clock> Clock for Demo<P> {
+    fn now(&mut self) -> Now {
+        match self.start {
+            Some(start) => {
+                let elapsed = self.rate * self.frames.saturating_sub(1) as i64;
+                Now {time: start + chrono::Duration::seconds(elapsed), offset_known: true}
+            },
+            None => self.platform.now()
+        }
+    }
+}
+impl<P: KeySource> KeySource for Demo<P> {
+    fn read_key(&mut self) -> Option<Key> {
+        match &mut self.script {
+            Some(script) => script.next_key(),
+            None => self.platform.read_key()
+        }
+    }
+}
+impl<P: Screen + FrameLog> Screen for Demo<P> {
+    fn clear(&mut self) {
+        self.log_frame();
+        self.frames += 1;
+        self.frame.clear();
+        self.platform.clear();
+    }
+    
+    fn print(&mut self, s: &str) {
+        if self.dump { self.frame.push_str(s); }
+        self.platform.print(s);
+    }
+    
+    fn columns(&mut self) -> usize {
+        self.platform.columns()
+    }
+    
+    fn block_glyphs_supported(&mut self) -> bool {
+        self.platform.block_glyphs_supported()
+    }
+    
+    fn next_text_mode(&mut self) -> bool {
+        self.platform.next_text_mode()
+    }
+}
+impl<P: VariableStore> VariableStore for Demo<P> {
+    fn get<'a>(&mut self, name: &str, buf: &'a mut [u8]) -> Option<&'a [u8]> {
+        match self.variables.get(name) {
+            Some(value) => {
+                let buf = buf.get_mut(..value.len())?;
+                buf.copy_from_slice(value);
+                Some(buf)
+            },
+            None if name == "totp_key" => None,
+            None => self.platform.get(name, buf)
+        }
+    }
+    
+    fn set(&mut self, name: &str, data: &[u8]) {
+        self.variables.insert(name.to_string(), data.to_vec());
+    }
+}
+impl<P> Entropy for Demo<P> {
+    type Rng<'a> = DemoRng where P: 'a;
+    
+    fn rng(&mut self) -> Option<DemoRng> {
+        self.seed += 1;
+        Some(DemoRng::new(self.seed))
+    }
+}
+
+
+/// SplitMix64, so generated secrets are reproducible.
+///
+/// It is marked [`CryptoRng`] only to satisfy the app's bounds; it is not fit
+/// for real secrets.
+pub struct DemoRng(u64);
+impl DemoRng {
+    pub fn new(seed: u64) -> DemoRng {
+        DemoRng(seed)
+    }
+}
+impl RngCore for DemoRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+    
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+    
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+impl CryptoRng for DemoRng {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parse_options() {
+        assert_eq!(DemoConfig::parse(""), Ok(None));
+        assert_eq!(DemoConfig::parse("\\EFI\\BOOT\\BOOTX64.EFI"), Ok(None));
+        assert_eq!(DemoConfig::parse("totp.efi --time 1234567890 --rate 2 --keys /EFI/totp-uefi/keys.txt --dump"), Ok(Some(DemoConfig {
+            time: Some(1234567890),
+            rate: 2,
+            keys: Some("\\EFI\\totp-uefi\\keys.txt".to_string()),
+            dump: true,
+        })));
+        assert_eq!(DemoConfig::parse("--dump"), Ok(Some(DemoConfig {dump: true, ..DemoConfig::default()})));
+        assert_eq!(DemoConfig::parse("--time soon"), Err(DemoError::InvalidOption("--time")));
+        assert_eq!(DemoConfig::parse("--rate"), Err(DemoError::InvalidOption("--rate")));
+    }
+    
+    #[test]
+    fn parse_script() {
+        let mut script = Script::parse("# set up\ntype ab\n\nkey BS\nkey F4\nkey ENTER\nkey ESC\nwait 2\nquit\n").unwrap();
+        assert_eq!(script.next_key(), Some(Key::Char('a')));
+        assert_eq!(script.next_key(), Some(Key::Char('b')));
+        assert_eq!(script.next_key(), Some(Key::Backspace));
+        assert_eq!(script.next_key(), Some(Key::Function(4)));
+        assert_eq!(script.next_key(), Some(Key::Enter));
+        assert_eq!(script.next_key(), Some(Key::Escape));
+        assert_eq!(script.next_key(), None);
+        assert_eq!(script.next_key(), None);
+        assert!(!script.finished);
+        assert_eq!(script.next_key(), None);
+        assert!(script.finished);
+    }
+    
+    #[test]
+    fn invalid_script() {
+        assert_eq!(Script::parse("type a\nkey F13").err(), Some(DemoError::InvalidScript(2)));
+        assert_eq!(Script::parse("wait soon").err(), Some(DemoError::InvalidScript(1)));
+        assert_eq!(Script::parse("reboot").err(), Some(DemoError::InvalidScript(1)));
+    }
+}
@@ -0,0 +1,42 @@
+use alloc::vec::Vec;
+use uefi::prelude::*;
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::proto::media::file::{File, FileAttribute, FileMode, RegularFile};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::CStr16;
+
+
+/// Opens a file on the volume the app was loaded from; `path` uses backslashes.
+pub fn open(table: &SystemTable<Boot>, image: Handle, path: &str, mode: FileMode) -> Option<RegularFile> {
+    let boot = table.boot_services();
+    let loaded_image = boot.open_protocol_exclusive::<LoadedImage>(image).ok()?;
+    let mut fs = boot.open_protocol_exclusive::<SimpleFileSystem>(loaded_image.device()).ok()?;
+    let mut root = fs.open_volume().ok()?;
+    
+    let mut path_buf = [0; 128];
+    let path = CStr16::from_str_with_buf(path, &mut path_buf).ok()?;
+    root.open(path, mode, FileAttribute::empty()).ok()?.into_regular_file()
+}
+
+/// Reads until `buf` is full or the file ends, returning the number of bytes read.
+pub fn read(file: &mut RegularFile, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => len += n
+        }
+    }
+    len
+}
+
+/// Reads a whole file, or returns `None` if it is missing or larger than `limit` bytes.
+pub fn read_to_vec(table: &SystemTable<Boot>, image: Handle, path: &str, limit: usize) -> Option<Vec<u8>> {
+    let mut file = open(table, image, path, FileMode::Read)?;
+    let mut data = alloc::vec![0; limit + 1];
+    let len = read(&mut file, &mut data);
+    if len > limit {return None;}
+    
+    data.truncate(len);
+    Some(data)
+}
@@ -0,0 +1,144 @@
+use alloc::string::{String, ToString};
+use chrono::{FixedOffset, TimeZone};
+use uefi::prelude::*;
+use uefi::proto::console::text::{Key as UefiKey, Output, ScanCode};
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::table::runtime::{VariableAttributes, VariableVendor};
+use uefi::CStr16;
+use zeroize::Zeroize;
+
+use totp_uefi::platform::{Clock, Entropy, FrameLog, Key, KeySource, Now, Screen, VariableStore};
+
+use crate::rng::FirmwareRng;
+
+
+fn vendor() -> VariableVendor {
+    VariableVendor(uefi::Guid::parse_or_panic("572e6927-177b-49ce-b761-2cdc60f42491"))
+}
+
+/// Writes text of any length, unlike a single `output_string` with a fixed buffer.
+fn output_chunked(out: &mut Output, s: &str) {
+    let mut buf = [0; 128];
+    let mut rest = s;
+    while !rest.is_empty() {
+        let end = rest.char_indices().nth(120).map_or(rest.len(), |(i, _)| i);
+        let chunk = CStr16::from_str_with_buf(&rest[..end], &mut buf).unwrap();
+        out.output_string(chunk).unwrap();
+        rest = &rest[end..];
+    }
+    buf.zeroize();
+}
+
+/// The real backend: UEFI console, runtime clock, NVRAM variables and firmware entropy.
+pub struct Firmware {
+    table: SystemTable<Boot>,
+}
+impl Firmware {
+    pub fn new(table: SystemTable<Boot>) -> Firmware {
+        Firmware {table: table}
+    }
+    
+    pub fn table(&self) -> &SystemTable<Boot> {
+        &self.table
+    }
+    
+    /// The command line the app was started with, including the image name if the shell passes it.
+    pub fn load_options(&self, image: Handle) -> Option<String> {
+        let loaded = self.table.boot_services().open_protocol_exclusive::<LoadedImage>(image).ok()?;
+        loaded.load_options_as_cstr16().ok().map(|options| options.to_string())
+    }
+}
+impl Clock for Firmware {
+    fn now(&mut self) -> Now {
+        let uefi_time = self.table.runtime_services().get_time().expect("timer failure");
+        let uefi_offset_available = uefi_time.time_zone().is_some();
+        let uefi_offset = uefi_time.time_zone().map_or(5 * 3600, |tz| tz * 60);
+        let chrono_offset = FixedOffset::east_opt(uefi_offset.into()).unwrap();
+        let chrono_time = chrono_offset.with_ymd_and_hms(
+            uefi_time.year() as i32, uefi_time.month() as u32,  uefi_time.day() as u32,
+            uefi_time.hour() as u32, uefi_time.minute() as u32, uefi_time.second() as u32
+        ).single().expect("UEFI returned invalid time");
+    
+        Now {time: chrono_time, offset_known: uefi_offset_available}
+    }
+}
+impl KeySource for Firmware {
+    fn read_key(&mut self) -> Option<Key> {
+        match self.table.stdin().read_key().expect("input device failure")? {
+            UefiKey::Printable(c) if u16::from(c) == 8 => Some(Key::Backspace),
+            UefiKey::Printable(c) if u16::from(c) == 13 => Some(Key::Enter),
+            UefiKey::Special(ScanCode::ESCAPE) => Some(Key::Escape),
+            UefiKey::Printable(c) => Some(Key::Char(char::from(c))),
+            UefiKey::Special(ScanCode(code)) => {
+                // FUNCTION_1 to FUNCTION_10 are contiguous, as are FUNCTION_11 and FUNCTION_12
+                let n = code.wrapping_sub(ScanCode::FUNCTION_1.0);
+                Some(if n < 12 {Key::Function(n as u8 + 1)} else {Key::Other})
+            }
+        }
+    }
+}
+impl Screen for Firmware {
+    fn clear(&mut self) {
+        self.table.stdout().clear().unwrap();
+    }
+    
+    fn print(&mut self, s: &str) {
+        let mut buf = [0; 255];
+        let out = CStr16::from_str_with_buf(s, &mut buf).unwrap();
+        self.table.stdout().output_string(out).unwrap();
+        buf.zeroize();
+    }
+    
+    fn columns(&mut self) -> usize {
+        self.table.stdout().current_mode().ok().flatten().map_or(80, |m| m.columns())
+    }
+    
+    /// Asks the console whether it can display the block glyphs of the big-digit fonts.
+    fn block_glyphs_supported(&mut self) -> bool {
+        self.table.stdout().test_string(cstr16!("█")).unwrap_or(false)
+    }
+    
+    fn next_text_mode(&mut self) -> bool {
+        let stdout = self.table.stdout();
+        let current = stdout.current_mode().ok().flatten().map_or(0, |m| m.index());
+        let next = stdout.modes().find(|m| m.index() > current).or_else(|| stdout.modes().next());
+        match next {
+            Some(mode) => stdout.set_mode(mode).is_ok(),
+            None => false
+        }
+    }
+}
+impl VariableStore for Firmware {
+    fn get<'a>(&mut self, name: &str, buf: &'a mut [u8]) -> Option<&'a [u8]> {
+        let mut vn_buf = [0; 32];
+        let var_name = CStr16::from_str_with_buf(name, &mut vn_buf).unwrap();
+    
+        self.table.runtime_services().get_variable(var_name, &vendor(), buf).ok().map(|(bytes, _attr)| bytes)
+    }
+    
+    fn set(&mut self, name: &str, data: &[u8]) {
+        let mut vn_buf = [0; 32];
+        let var_name = CStr16::from_str_with_buf(name, &mut vn_buf).unwrap();
+    
+        let attr = VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS;
+    
+        self.table.runtime_services().set_variable(var_name, &vendor(), attr, data).unwrap();
+    }
+}
+impl FrameLog for Firmware {
+    /// Frames go to standard error, so a harness can capture them apart from the screen.
+    fn log_frame(&mut self, index: u64, frame: &str) {
+        let header = "=== frame ".to_string() + &index.to_string() + " ===\r\n";
+        let stderr = self.table.stderr();
+        output_chunked(stderr, &header);
+        output_chunked(stderr, frame);
+        output_chunked(stderr, "\r\n");
+    }
+}
+impl Entropy for Firmware {
+    type Rng<'a> = FirmwareRng<'a>;
+    
+    fn rng(&mut self) -> Option<FirmwareRng<'_>> {
+        FirmwareRng::new(self.table.boot_services())
+    }
+}
@@ -0,0 +1,208 @@
+/// Big-digit glyphs; every glyph row of a font has the same number of columns.
+pub struct Font {
+    glyphs: &'static [[&'static str; 10]],
+    pub width: usize,
+}
+impl Font {
+    pub fn height(&self) -> usize {
+        self.glyphs.len()
+    }
+    
+    pub fn digit_row(&self, digit: char, row: usize) -> &'static str {
+        match digit.to_digit(10) {
+            Some(n) => self.glyphs[row][n as usize],
+            None => ""
+        }
+    }
+}
+
+const WIDE_GLYPHS: [[&str; 10]; 8] = [
+  ["  ██████    ","    ██      ","  ██████    ","  ██████    ","    ██      ","██████████  ","    ████    ","██████████  ","  ██████    ","  ██████    "],
+  ["██      ██  ","    ██      ","██      ██  ","██      ██  ","    ██      ","██          ","  ██        ","██      ██  ","██      ██  ","██      ██  "],
+  ["██    ████  ","  ████      ","        ██  ","        ██  ","  ██        ","██          ","██          ","      ██    ","██      ██  ","██      ██  "],
+  ["██  ██  ██  ","    ██      ","        ██  ","    ████    ","  ██        ","████████    ","████████    ","      ██    ","  ██████    ","██      ██  "],
+  ["██  ██  ██  ","    ██      ","      ██    ","        ██  ","  ██  ██    ","        ██  ","██      ██  ","    ██      ","██      ██  ","  ████████  "],
+  ["████    ██  ","    ██      ","  ████      ","        ██  ","██    ██    ","        ██  ","██      ██  ","    ██      ","██      ██  ","        ██  "],
+  ["██      ██  ","    ██      ","██          ","██      ██  ","██████████  ","██      ██  ","██      ██  ","  ██        ","██      ██  ","      ██    "],
+  ["  ██████    ","  ██████    ","██████████  ","  ██████    ","      ██    ","  ██████    ","  ██████    ","  ██        ","  ██████    ","  ████      "],
+];
+const NARROW_GLYPHS: [[&str; 10]; 8] = [
+  [" ███  ","  █   "," ███  "," ███  ","  █   ","█████ ","  ██  ","█████ "," ███  "," ███  "],
+  ["█   █ ","  █   ","█   █ ","█   █ ","  █   ","█     "," █    ","█   █ ","█   █ ","█   █ "],
+  ["█  ██ "," ██   ","    █ ","    █ "," █    ","█     ","█     ","   █  ","█   █ ","█   █ "],
+  ["█ █ █ ","  █   ","    █ ","  ██  "," █    ","████  ","████  ","   █  "," ███  ","█   █ "],
+  ["█ █ █ ","  █   ","   █  ","    █ "," █ █  ","    █ ","█   █ ","  █   ","█   █ "," ████ "],
+  ["██  █ ","  █   "," ██   ","    █ ","█  █  ","    █ ","█   █ ","  █   ","█   █ ","    █ "],
+  ["█   █ ","  █   ","█     ","█   █ ","█████ ","█   █ ","█   █ "," █    ","█   █ ","   █  "],
+  [" ███  "," ███  ","█████ "," ███  ","   █  "," ███  "," ███  "," █    "," ███  "," ██   "],
+];
+const WIDE_ASCII_GLYPHS: [[&str; 10]; 8] = [
+  ["  ######    ","    ##      ","  ######    ","  ######    ","    ##      ","##########  ","    ####    ","##########  ","  ######    ","  ######    "],
+  ["##      ##  ","    ##      ","##      ##  ","##      ##  ","    ##      ","##          ","  ##        ","##      ##  ","##      ##  ","##      ##  "],
+  ["##    ####  ","  ####      ","        ##  ","        ##  ","  ##        ","##          ","##          ","      ##    ","##      ##  ","##      ##  "],
+  ["##  ##  ##  ","    ##      ","        ##  ","    ####    ","  ##        ","########    ","########    ","      ##    ","  ######    ","##      ##  "],
+  ["##  ##  ##  ","    ##      ","      ##    ","        ##  ","  ##  ##    ","        ##  ","##      ##  ","    ##      ","##      ##  ","  ########  "],
+  ["####    ##  ","    ##      ","  ####      ","        ##  ","##    ##    ","        ##  ","##      ##  ","    ##      ","##      ##  ","        ##  "],
+  ["##      ##  ","    ##      ","##          ","##      ##  ","##########  ","##      ##  ","##      ##  ","  ##        ","##      ##  ","      ##    "],
+  ["  ######    ","  ######    ","##########  ","  ######    ","      ##    ","  ######    ","  ######    ","  ##        ","  ######    ","  ####      "],
+];
+const NARROW_ASCII_GLYPHS: [[&str; 10]; 8] = [
+  [" ###  ","  #   "," ###  "," ###  ","  #   ","##### ","  ##  ","##### "," ###  "," ###  "],
+  ["#   # ","  #   ","#   # ","#   # ","  #   ","#     "," #    ","#   # ","#   # ","#   # "],
+  ["#  ## "," ##   ","    # ","    # "," #    ","#     ","#     ","   #  ","#   # ","#   # "],
+  ["# # # ","  #   ","    # ","  ##  "," #    ","####  ","####  ","   #  "," ###  ","#   # "],
+  ["# # # ","  #   ","   #  ","    # "," # #  ","    # ","#   # ","  #   ","#   # "," #### "],
+  ["##  # ","  #   "," ##   ","    # ","#  #  ","    # ","#   # ","  #   ","#   # ","    # "],
+  ["#   # ","  #   ","#     ","#   # ","##### ","#   # ","#   # "," #    ","#   # ","   #  "],
+  [" ###  "," ###  ","##### "," ###  ","   #  "," ###  "," ###  "," #    "," ###  "," ##   "],
+];
+
+/// Two columns per pixel, which looks square on most consoles.
+pub const WIDE: Font = Font {glyphs: &WIDE_GLYPHS, width: 12};
+/// One column per pixel, for codes that do not fit in `WIDE`.
+pub const NARROW: Font = Font {glyphs: &NARROW_GLYPHS, width: 6};
+/// Same shapes drawn with `#`, for consoles that cannot display U+2588.
+pub const WIDE_ASCII: Font = Font {glyphs: &WIDE_ASCII_GLYPHS, width: 12};
+pub const NARROW_ASCII: Font = Font {glyphs: &NARROW_ASCII_GLYPHS, width: 6};
+/// The digits themselves, as a last resort on very small consoles.
+pub const PLAIN: Font = Font {glyphs: &[["0","1","2","3","4","5","6","7","8","9"]], width: 1};
+
+
+/// Where and how to draw a code of a given length.
+pub struct Layout {
+    pub font:   &'static Font,
+    /// Number of digits per group; groups are separated by `gap` columns.
+    pub group:  usize,
+    pub gap:    usize,
+    /// Columns left of the code, which centres it on screen.
+    pub indent: usize,
+}
+impl Layout {
+    /// Picks the largest rendering of `digits` digits that fits in `columns` columns,
+    /// using ASCII art unless the console can display `█`.
+    pub fn choose(columns: usize, digits: usize, block_glyphs: bool) -> Layout {
+        Layout::choose_smaller(columns, digits, block_glyphs, usize::MAX)
+    }
+    
+    /// Like `choose`, but only considers fonts narrower than `width` columns per digit.
+    pub fn choose_smaller(columns: usize, digits: usize, block_glyphs: bool, width: usize) -> Layout {
+        let (wide, narrow) = if block_glyphs {(&WIDE, &NARROW)} else {(&WIDE_ASCII, &NARROW_ASCII)};
+        let group = digits.div_ceil(2);
+        let candidates = [(wide, group), (wide, digits), (narrow, group), (narrow, digits)];
+        
+        for (font, group) in candidates {
+            if font.width >= width {continue;}
+            let layout = Layout::new(font, group, digits, columns);
+            // the last column is kept free, writing there wraps on some consoles
+            if layout.width(digits) < columns {return layout;}
+        }
+        Layout::new(&PLAIN, group, digits, columns)
+    }
+    
+    fn new(font: &'static Font, group: usize, digits: usize, columns: usize) -> Layout {
+        let gap = (font.width / 2).max(1);
+        let mut this = Layout {font, group, gap, indent: 0};
+        this.indent = columns.saturating_sub(this.width(digits)) / 2;
+        this
+    }
+    
+    /// Total columns taken by `digits` digits.
+    pub fn width(&self, digits: usize) -> usize {
+        let gaps = if digits == 0 {0} else {(digits - 1) / self.group};
+        digits * self.font.width + gaps * self.gap
+    }
+    
+    /// Whether a group separator goes before the digit at `index`.
+    pub fn gap_before(&self, index: usize) -> bool {
+        index > 0 && index % self.group == 0
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, NARROW, NARROW_ASCII, PLAIN, WIDE, WIDE_ASCII};
+
+    #[test]
+    fn glyph_rows_have_font_width() {
+        for font in [&WIDE, &NARROW, &WIDE_ASCII, &NARROW_ASCII, &PLAIN] {
+            for row in 0..font.height() {
+                for digit in '0'..='9' {
+                    assert_eq!(font.digit_row(digit, row).chars().count(), font.width);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn six_digits_on_80_columns() {
+        let layout = Layout::choose(80, 6, true);
+        assert_eq!(layout.font.width, WIDE.width);
+        assert_eq!(layout.group, 3);
+        assert_eq!(layout.width(6), 78);
+        assert_eq!(layout.indent, 1);
+    }
+
+    #[test]
+    fn long_codes_fit_80_columns() {
+        for digits in 6..=8 {
+            let layout = Layout::choose(80, digits, true);
+            assert!(layout.font.width > PLAIN.width);
+            assert!(layout.indent + layout.width(digits) < 80);
+        }
+        assert_eq!(Layout::choose(80, 8, true).font.width, NARROW.width);
+    }
+
+    #[test]
+    fn wide_font_on_large_modes() {
+        // grouping would need 102 columns, so the digits are drawn back to back
+        let layout = Layout::choose(100, 8, true);
+        assert_eq!(layout.font.width, WIDE.width);
+        assert_eq!(layout.group, 8);
+        assert_eq!(layout.width(8), 96);
+        assert_eq!(layout.indent, 2);
+    }
+
+    #[test]
+    fn plain_text_on_tiny_consoles() {
+        let layout = Layout::choose(20, 8, true);
+        assert_eq!(layout.font.width, PLAIN.width);
+        assert_eq!(layout.width(8), 9);
+        assert_eq!(layout.indent, 5);
+    }
+
+    #[test]
+    fn groups() {
+        let layout = Layout::choose(80, 7, true);
+        assert_eq!(layout.group, 4);
+        assert!(!layout.gap_before(0));
+        assert!(layout.gap_before(4));
+        assert!(!layout.gap_before(5));
+    }
+    #[test]
+    fn ascii_fallback() {
+        let layout = Layout::choose(80, 6, false);
+        assert_eq!(layout.font.digit_row('0', 0), "  ######    ");
+        assert_eq!(Layout::choose(80, 8, false).font.digit_row('1', 7), " ###  ");
+        for row in 0..WIDE_ASCII.height() {
+            for digit in '0'..='9' {
+                assert_eq!(
+                    WIDE_ASCII.digit_row(digit, row).replace('#', "█"),
+                    WIDE.digit_row(digit, row)
+                );
+                assert!(NARROW_ASCII.digit_row(digit, row).is_ascii());
+            }
+        }
+    }
+    #[test]
+    fn smaller_preview() {
+        let layout = Layout::choose(80, 6, true);
+        let preview = Layout::choose_smaller(80, 6, true, layout.font.width);
+        assert_eq!(preview.font.width, NARROW.width);
+        assert_eq!(preview.group, 3);
+
+        let layout = Layout::choose(80, 8, true);
+        let preview = Layout::choose_smaller(80, 8, true, layout.font.width);
+        assert_eq!(preview.font.width, PLAIN.width);
+    }
+}
@@ -0,0 +1,143 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+
+pub const BASE32_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 secret typed at the console or loaded from NVRAM.
+///
+/// Characters live in a fixed inline buffer, so editing never reallocates and
+/// leaves no stale copies on the heap; the buffer is wiped on drop.
+pub struct SecretKey {
+    buf: [u8; SecretKey::CAPACITY],
+    len: usize,
+}
+impl SecretKey {
+    pub const CAPACITY: usize = 60;
+
+    pub fn new() -> SecretKey {
+        SecretKey {buf: [0; SecretKey::CAPACITY], len: 0}
+    }
+
+    /// Copies a stored key, case-insensitively like `push`, returning an empty one
+    /// if `bytes` is not a valid base32 key.
+    pub fn from_bytes(bytes: &[u8]) -> SecretKey {
+        let mut this = SecretKey::new();
+        if bytes.len() > SecretKey::CAPACITY {return this;}
+
+        for &b in bytes {
+            if !this.push(b as char) {
+                this.zeroize();
+                return this;
+            }
+        }
+        this
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only base32 characters are ever stored
+        core::str::from_utf8(self.as_bytes()).unwrap()
+    }
+
+    /// Appends a base32 character (case-insensitive); returns false if it was rejected.
+    pub fn push(&mut self, c: char) -> bool {
+        let c = c.to_ascii_uppercase();
+        if self.len == SecretKey::CAPACITY {return false;}
+        if !c.is_ascii() || !BASE32_CHARS.contains(c) {return false;}
+
+        self.buf[self.len] = c as u8;
+        self.len += 1;
+        true
+    }
+
+    pub fn pop(&mut self) -> bool {
+        if self.is_empty() {return false;}
+
+        self.len -= 1;
+        self.buf[self.len] = 0;
+        true
+    }
+}
+impl Default for SecretKey {
+    fn default() -> SecretKey {
+        SecretKey::new()
+    }
+}
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        self.buf.zeroize();
+        self.len = 0;
+    }
+}
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+impl ZeroizeOnDrop for SecretKey {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::SecretKey;
+    use zeroize::Zeroize;
+
+    #[test]
+    fn push_pop() {
+        let mut key = SecretKey::new();
+        assert!(key.push('a'));
+        assert!(key.push('B'));
+        assert!(key.push('7'));
+        assert!(!key.push('1'));
+        assert!(!key.push('='));
+        assert!(!key.push('ж'));
+        assert_eq!(key.as_str(), "AB7");
+
+        assert!(key.pop());
+        assert_eq!(key.as_str(), "AB");
+        assert_eq!(key.buf[2], 0);
+        assert!(key.pop() && key.pop());
+        assert!(!key.pop());
+        assert!(key.is_empty());
+    }
+
+    #[test]
+    fn capacity() {
+        let mut key = SecretKey::new();
+        for _ in 0..SecretKey::CAPACITY {
+            assert!(key.push('A'));
+        }
+        assert!(!key.push('A'));
+        assert_eq!(key.len(), SecretKey::CAPACITY);
+    }
+
+    #[test]
+    fn from_bytes() {
+        assert_eq!(SecretKey::from_bytes(b"KRSXG5CT").as_str(), "KRSXG5CT");
+        assert_eq!(SecretKey::from_bytes(b"krsxg5ct").as_str(), "KRSXG5CT");
+        assert_eq!(SecretKey::from_bytes(b"KrSxG5cT").as_str(), "KRSXG5CT");
+        assert!(SecretKey::from_bytes(b"KRSX-G5CT").is_empty());
+        assert!(SecretKey::from_bytes(b"KRSX\xc3\xa9").is_empty());
+        assert!(SecretKey::from_bytes(b"KRSX-G5CT").buf.iter().all(|&b| b == 0));
+        assert!(SecretKey::from_bytes(&[b'A'; SecretKey::CAPACITY + 1]).is_empty());
+    }
+
+    #[test]
+    fn zeroize_wipes_buffer() {
+        let mut key = SecretKey::from_bytes(b"KRSXG5CTMVRXEZLU");
+        key.zeroize();
+        assert!(key.is_empty());
+        assert!(key.buf.iter().all(|&b| b == 0));
+    }
+}
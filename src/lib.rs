@@ -0,0 +1,15 @@
+//! The TOTP screen itself, independent of the firmware it runs on.
+//!
+//! `main.rs` drives it with the UEFI backend; the `sim` crate runs the same code
+//! in a terminal with an in-memory backend.
+#![no_std]
+#[cfg(test)]
+extern crate std;
+
+extern crate alloc;
+
+pub mod app;
+pub mod demo;
+pub mod font;
+pub mod key;
+pub mod platform;
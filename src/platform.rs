@@ -0,0 +1,74 @@
+//! Firmware services used by the UI, so it can run on UEFI or in the host simulator.
+use core::convert::Infallible;
+use chrono::{DateTime, FixedOffset};
+use rand_core::{CryptoRng, RngCore};
+use totp_rs::TimeSource;
+
+
+/// A key press, reduced to what the UI reacts to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Enter,
+    Escape,
+    /// `Function(n)` is the key labelled F*n*.
+    Function(u8),
+    Other,
+}
+
+/// Local wall-clock time; `offset_known` is false when the offset is a guess.
+pub struct Now {
+    pub time: DateTime<FixedOffset>,
+    pub offset_known: bool,
+}
+
+/// A snapshot as a time source, so every code on a frame is for the instant it prints.
+impl TimeSource for Now {
+    type Error = Infallible;
+    fn now(&self) -> Result<u64, Infallible> {
+        Ok(self.time.timestamp() as u64)
+    }
+}
+
+pub trait Clock {
+    fn now(&mut self) -> Now;
+}
+
+pub trait KeySource {
+    /// Returns the next pending key press without waiting.
+    fn read_key(&mut self) -> Option<Key>;
+}
+
+pub trait Screen {
+    fn clear(&mut self);
+    fn print(&mut self, s: &str);
+    /// Width of the current text mode.
+    fn columns(&mut self) -> usize;
+    fn block_glyphs_supported(&mut self) -> bool;
+    /// Switches to the next text mode, wrapping around; returns false if nothing changed.
+    fn next_text_mode(&mut self) -> bool;
+}
+
+/// Persistent storage for the app's settings, keyed by variable name.
+pub trait VariableStore {
+    /// Reads `name` into `buf`, returning the stored bytes or `None` if missing or too large.
+    fn get<'a>(&mut self, name: &str, buf: &'a mut [u8]) -> Option<&'a [u8]>;
+    fn set(&mut self, name: &str, data: &[u8]);
+}
+
+pub trait Entropy {
+    type Rng<'a>: RngCore + CryptoRng where Self: 'a;
+
+    /// Returns `None` if no random source is available.
+    fn rng(&mut self) -> Option<Self::Rng<'_>>;
+}
+
+/// Sink for the frames dumped in demo mode, kept apart from the screen itself.
+pub trait FrameLog {
+    fn log_frame(&mut self, index: u64, frame: &str);
+}
+
+/// Everything the app needs from its environment.
+pub trait Platform: Clock + KeySource + Screen + VariableStore + Entropy {}
+impl<T: Clock + KeySource + Screen + VariableStore + Entropy> Platform for T {}
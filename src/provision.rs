@@ -0,0 +1,39 @@
+use uefi::prelude::*;
+use uefi::proto::media::file::{File, FileMode};
+use zeroize::{Zeroize, Zeroizing};
+
+use totp_bundle::{BundleError, Payload};
+use totp_rs::Secret;
+use totp_uefi::key::SecretKey;
+
+use crate::esp;
+
+
+/// Reads and removes the bundle left by `totp-provision` on the volume the app was loaded from.
+///
+/// Returns the number of bytes read into `buf`, or `None` if there is no bundle.
+/// The file is deleted even if it later fails verification, so a bad bundle is
+/// only reported once.
+fn take_bundle(table: &SystemTable<Boot>, image: Handle, buf: &mut [u8]) -> Option<usize> {
+    let mut file = esp::open(table, image, &totp_bundle::ESP_PATH.join("\\"), FileMode::ReadWrite)?;
+    let len = esp::read(&mut file, buf);
+    let _ = file.delete();
+    Some(len)
+}
+
+/// Verifies a pending provisioning bundle against the `current` secret.
+///
+/// Returns `None` if no bundle was waiting on the ESP.
+pub fn ingest(table: &SystemTable<Boot>, image: Handle, current: &SecretKey) -> Option<Result<Payload, BundleError>> {
+    // one spare byte so oversized files are rejected instead of truncated
+    let mut buf = [0; totp_bundle::MAX_LEN + 1];
+    let len = take_bundle(table, image, &mut buf)?;
+
+    // without a usable secret anyone could seal a bundle, HMAC takes an empty key
+    let result = match Secret::Encoded(current.as_str().into()).to_bytes() {
+        Ok(current) if !current.is_empty() => totp_bundle::open(&Zeroizing::new(current), &buf[..len]),
+        _ => Err(BundleError::NoCurrentSecret)
+    };
+    buf.zeroize();
+    Some(result)
+}
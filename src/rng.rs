@@ -0,0 +1,82 @@
+use core::num::NonZeroU32;
+
+use rand_core::{CryptoRng, RngCore, Error};
+use uefi::proto::rng::Rng;
+use uefi::table::boot::{BootServices, ScopedProtocol};
+use zeroize::Zeroize;
+
+
+/// Entropy from `EFI_RNG_PROTOCOL`, falling back to RDRAND when the firmware does not provide it.
+pub struct FirmwareRng<'a> {
+    protocol: Option<ScopedProtocol<'a, Rng>>,
+}
+impl<'a> FirmwareRng<'a> {
+    /// Returns `None` if neither entropy source is available.
+    pub fn new(boot_services: &'a BootServices) -> Option<FirmwareRng<'a>> {
+        let protocol = boot_services.get_handle_for_protocol::<Rng>().ok()
+            .and_then(|handle| boot_services.open_protocol_exclusive::<Rng>(handle).ok());
+        
+        if protocol.is_none() && !rdrand_available() {return None;}
+        Some(FirmwareRng {protocol})
+    }
+}
+impl RngCore for FirmwareRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+    
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest).expect("entropy source failure")
+    }
+    
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        if let Some(protocol) = &mut self.protocol {
+            if protocol.get_rng(None, dest).is_ok() {return Ok(());}
+        }
+        if rdrand_available() && rdrand_fill(dest) {return Ok(());}
+        
+        Err(Error::from(NonZeroU32::new(Error::CUSTOM_START).unwrap()))
+    }
+}
+impl CryptoRng for FirmwareRng<'_> {}
+
+
+#[cfg(target_arch = "x86_64")]
+fn rdrand_available() -> bool {
+    // CPUID.01H:ECX.RDRAND[bit 30]
+    unsafe { core::arch::x86_64::__cpuid(1).ecx & (1 << 30) != 0 }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn rdrand_available() -> bool {
+    false
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rdrand_fill(dest: &mut [u8]) -> bool {
+    #[target_feature(enable = "rdrand")]
+    unsafe fn step(value: &mut u64) -> bool {
+        // Intel recommends retrying a few times before treating an underflow as failure
+        (0..10).any(|_| core::arch::x86_64::_rdrand64_step(value) == 1)
+    }
+    
+    let mut value = 0u64;
+    for chunk in dest.chunks_mut(8) {
+        if !unsafe { step(&mut value) } {
+            value.zeroize();
+            return false;
+        }
+        chunk.copy_from_slice(&value.to_ne_bytes()[..chunk.len()]);
+    }
+    value.zeroize();
+    true
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn rdrand_fill(_dest: &mut [u8]) -> bool {
+    false
+}
@@ -0,0 +1,52 @@
+#![feature(test)]
+extern crate test;
+
+use test::Bencher;
+use totp_rs::{Algorithm, TOTP};
+
+#[cfg(not(feature = "otpauth"))]
+fn totp(algorithm: Algorithm, skew: u8) -> TOTP {
+    TOTP::new_unchecked(algorithm, 8, skew, 30, b"12345678901234567890".to_vec())
+}
+
+#[cfg(feature = "otpauth")]
+fn totp(algorithm: Algorithm, skew: u8) -> TOTP {
+    let secret = b"12345678901234567890".to_vec();
+    TOTP::new_unchecked(algorithm, 8, skew, 30, secret, None, String::new())
+}
+
+macro_rules! bench_totp {
+    ($($algorithm:expr => $check:ident $check_prepared:ident $generate:ident $generate_steps:ident;)*) => {$(
+        #[bench]
+        fn $check(b: &mut Bencher) {
+            let totp = totp($algorithm, 10);
+            b.iter(|| totp.check(test::black_box("00000000"), 1111111109));
+        }
+
+        #[bench]
+        fn $check_prepared(b: &mut Bencher) {
+            let totp = totp($algorithm, 10);
+            let prepared = totp.prepare();
+            b.iter(|| prepared.check(test::black_box("00000000"), 1111111109));
+        }
+
+        #[bench]
+        fn $generate(b: &mut Bencher) {
+            let totp = totp($algorithm, 0);
+            b.iter(|| (0..100).map(|i| totp.generate_u32(i * 30)).sum::<u32>());
+        }
+
+        #[bench]
+        fn $generate_steps(b: &mut Bencher) {
+            let totp = totp($algorithm, 0);
+            let prepared = totp.prepare();
+            b.iter(|| prepared.generate_steps(0..100).sum::<u32>());
+        }
+    )*};
+}
+
+bench_totp!(
+    Algorithm::SHA1 => sha1_check_skew10 sha1_check_skew10_prepared sha1_generate_100 sha1_generate_steps_100;
+    Algorithm::SHA256 => sha256_check_skew10 sha256_check_skew10_prepared sha256_generate_100 sha256_generate_steps_100;
+    Algorithm::SHA512 => sha512_check_skew10 sha512_check_skew10_prepared sha512_generate_100 sha512_generate_steps_100;
+);
@@ -1,9 +1,9 @@
-#[cfg(all(feature = "gen_secret", feature = "otpauth"))]
+#[cfg(all(feature = "gen_secret", feature = "std", feature = "otpauth"))]
 use totp_rs::{Algorithm, Secret, TOTP};
 
-#[cfg(all(feature = "gen_secret", feature = "otpauth"))]
+#[cfg(all(feature = "gen_secret", feature = "std", feature = "otpauth"))]
 fn main() {
-    let secret = Secret::generate_secret();
+    let secret = Secret::generate_secret(&mut rand::thread_rng());
 
     let totp = TOTP::new(
         Algorithm::SHA1,
@@ -24,5 +24,5 @@ fn main() {
     )
 }
 
-#[cfg(not(all(feature = "gen_secret", feature = "otpauth")))]
+#[cfg(not(all(feature = "gen_secret", feature = "std", feature = "otpauth")))]
 fn main() {}
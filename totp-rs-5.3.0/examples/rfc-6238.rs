@@ -1,5 +1,11 @@
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 use totp_rs::{Rfc6238, TOTP};
 
+/// The system clock as a [TimeSource](totp_rs::TimeSource), which examples have even without feature `std`
+fn now() -> Result<u64, SystemTimeError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
 #[cfg(feature = "otpauth")]
 fn main() {
     let mut rfc = Rfc6238::with_defaults("totp-sercret-123".as_bytes().to_vec()).unwrap();
@@ -11,7 +17,7 @@ fn main() {
 
     // create a TOTP from rfc
     let totp = TOTP::from_rfc6238(rfc).unwrap();
-    let code = totp.generate_current().unwrap();
+    let code = totp.generate_current_with(&now).unwrap();
     println!("code: {}", code);
 }
 
@@ -24,6 +30,6 @@ fn main() {
 
     // create a TOTP from rfc
     let totp = TOTP::from_rfc6238(rfc).unwrap();
-    let code = totp.generate_current().unwrap();
+    let code = totp.generate_current_with(&now).unwrap();
     println!("code: {}", code);
 }
@@ -1,9 +1,15 @@
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 use totp_rs::{Algorithm, Secret, TOTP};
 
+/// The system clock as a [TimeSource](totp_rs::TimeSource), which examples have even without feature `std`
+fn now() -> Result<u64, SystemTimeError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
 #[cfg(feature = "otpauth")]
 fn main() {
     // create TOTP from base32 secret
-    let secret_b32 = Secret::Encoded(String::from("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG"));
+    let secret_b32 = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     let totp_b32 = TOTP::new(
         Algorithm::SHA1,
         6,
@@ -17,12 +23,12 @@ fn main() {
 
     println!(
         "base32 {} ; raw {}",
-        secret_b32,
-        secret_b32.to_raw().unwrap()
+        secret_b32.reveal(),
+        secret_b32.to_raw().unwrap().reveal()
     );
     println!(
         "code from base32:\t{}",
-        totp_b32.generate_current().unwrap()
+        totp_b32.generate_current_with(&now).unwrap()
     );
 
     // create TOTP from raw binary value
@@ -30,7 +36,7 @@ fn main() {
         0x70, 0x6c, 0x61, 0x69, 0x6e, 0x2d, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0x2d, 0x73, 0x65,
         0x63, 0x72, 0x65, 0x74, 0x2d, 0x31, 0x32, 0x33,
     ];
-    let secret_raw = Secret::Raw(secret.to_vec());
+    let secret_raw = Secret::Raw(secret.to_vec().into());
     let totp_raw = TOTP::new(
         Algorithm::SHA1,
         6,
@@ -42,27 +48,31 @@ fn main() {
     )
     .unwrap();
 
-    println!("raw {} ; base32 {}", secret_raw, secret_raw.to_encoded());
+    println!(
+        "raw {} ; base32 {}",
+        secret_raw.reveal(),
+        secret_raw.to_encoded().reveal()
+    );
     println!(
         "code from raw secret:\t{}",
-        totp_raw.generate_current().unwrap()
+        totp_raw.generate_current_with(&now).unwrap()
     );
 }
 
 #[cfg(not(feature = "otpauth"))]
 fn main() {
     // create TOTP from base32 secret
-    let secret_b32 = Secret::Encoded(String::from("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG"));
+    let secret_b32 = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     let totp_b32 = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret_b32.to_bytes().unwrap()).unwrap();
 
     println!(
         "base32 {} ; raw {}",
-        secret_b32,
-        secret_b32.to_raw().unwrap()
+        secret_b32.reveal(),
+        secret_b32.to_raw().unwrap().reveal()
     );
     println!(
         "code from base32:\t{}",
-        totp_b32.generate_current().unwrap()
+        totp_b32.generate_current_with(&now).unwrap()
     );
 
     // create TOTP from raw binary value
@@ -70,12 +80,16 @@ fn main() {
         0x70, 0x6c, 0x61, 0x69, 0x6e, 0x2d, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0x2d, 0x73, 0x65,
         0x63, 0x72, 0x65, 0x74, 0x2d, 0x31, 0x32, 0x33,
     ];
-    let secret_raw = Secret::Raw(secret.to_vec());
+    let secret_raw = Secret::Raw(secret.to_vec().into());
     let totp_raw = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret_raw.to_bytes().unwrap()).unwrap();
 
-    println!("raw {} ; base32 {}", secret_raw, secret_raw.to_encoded());
+    println!(
+        "raw {} ; base32 {}",
+        secret_raw.reveal(),
+        secret_raw.to_encoded().reveal()
+    );
     println!(
         "code from raw secret:\t{}",
-        totp_raw.generate_current().unwrap()
+        totp_raw.generate_current_with(&now).unwrap()
     );
 }
@@ -1,11 +1,19 @@
 #[cfg(feature = "steam")]
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+#[cfg(feature = "steam")]
 use totp_rs::{Secret, TOTP};
 
+#[cfg(feature = "steam")]
+/// The system clock as a [TimeSource](totp_rs::TimeSource), which examples have even without feature `std`
+fn now() -> Result<u64, SystemTimeError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
 #[cfg(feature = "steam")]
 #[cfg(feature = "otpauth")]
 fn main() {
     // create TOTP from base32 secret
-    let secret_b32 = Secret::Encoded(String::from("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG"));
+    let secret_b32 = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     let totp_b32 = TOTP::new_steam(secret_b32.to_bytes().unwrap(), "user-account".to_string());
 
     println!(
@@ -15,7 +23,7 @@ fn main() {
     );
     println!(
         "code from base32:\t{}",
-        totp_b32.generate_current().unwrap()
+        totp_b32.generate_current_with(&now).unwrap()
     );
 }
 
@@ -23,7 +31,7 @@ fn main() {
 #[cfg(not(feature = "otpauth"))]
 fn main() {
     // create TOTP from base32 secret
-    let secret_b32 = Secret::Encoded(String::from("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG"));
+    let secret_b32 = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     let totp_b32 = TOTP::new_steam(secret_b32.to_bytes().unwrap());
 
     println!(
@@ -33,7 +41,7 @@ fn main() {
     );
     println!(
         "code from base32:\t{}",
-        totp_b32.generate_current().unwrap()
+        totp_b32.generate_current_with(&now).unwrap()
     );
 }
 
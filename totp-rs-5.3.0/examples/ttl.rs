@@ -1,5 +1,11 @@
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 use totp_rs::{Algorithm, TOTP};
 
+/// The system clock as a [TimeSource](totp_rs::TimeSource), which examples have even without feature `std`
+fn now() -> Result<u64, SystemTimeError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
 #[cfg(not(feature = "otpauth"))]
 fn main() {
     let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, "my-secret".as_bytes().to_vec()).unwrap();
@@ -7,9 +13,9 @@ fn main() {
     loop {
         println!(
             "code {}\t ttl {}\t valid until: {}",
-            totp.generate_current().unwrap(),
-            totp.ttl().unwrap(),
-            totp.next_step_current().unwrap()
+            totp.generate_current_with(&now).unwrap(),
+            totp.ttl_with(&now).unwrap(),
+            totp.next_step_current_with(&now).unwrap()
         );
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
@@ -31,9 +37,9 @@ fn main() {
     loop {
         println!(
             "code {}\t ttl {}\t valid until: {}",
-            totp.generate_current().unwrap(),
-            totp.ttl().unwrap(),
-            totp.next_step_current().unwrap()
+            totp.generate_current_with(&now).unwrap(),
+            totp.ttl_with(&now).unwrap(),
+            totp.next_step_current_with(&now).unwrap()
         );
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
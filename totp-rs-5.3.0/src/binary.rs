@@ -0,0 +1,543 @@
+//! Compact binary records of a [TOTP], for storage too small for serde formats or URLs.
+//!
+//! Firmware and host tools built with different features read and write the same records.
+//! Without feature `otpauth`, issuer and account name are written empty and skipped when read.
+//!
+//! ```text
+//! version | algorithm | digits | skew | step | t0 | encoding | secret | issuer | account name | crc32
+//! ```
+//!
+//! The version, algorithm, digits and skew take a byte each. `step` and `t0` are LEB128 varints.
+//! The secret and account name are prefixed with their length in a byte, and so is the issuer,
+//! plus one so that 0 means none. The encoding is a byte, 0 for the default of the algorithm,
+//! followed by its parameters for custom alphabets and groups. A group holds one encoding, which
+//! cannot be another group. The CRC-32 (IEEE) of everything before it ends the record, big-endian.
+//!
+//! ```rust
+//! use totp_rs::TOTP;
+//!
+//! let totp = TOTP::builder().secret(b"TestSecretSuperSecret".to_vec()).build().unwrap();
+//! let record = totp.to_bytes().unwrap();
+//! assert_eq!(record.len(), 35);
+//! assert_eq!(TOTP::from_bytes(&record).unwrap(), totp);
+//! ```
+
+use alloc::boxed::Box;
+#[cfg(feature = "otpauth")]
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use zeroize::Zeroizing;
+
+use crate::{Algorithm, Encoding, Symbols, TOTP};
+
+/// Version of the records written by [TOTP::to_bytes]
+pub const BINARY_VERSION: u8 = 1;
+
+const CHECKSUM_LEN: usize = 4;
+
+/// Why a [TOTP] could not be written to or read from a binary record
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BinaryError {
+    /// The record is truncated, too long, or a field doesn't parse.
+    Malformed,
+    /// The record was written by a newer format version.
+    UnsupportedVersion(u8),
+    /// The checksum doesn't match, the record is corrupted.
+    Checksum,
+    /// Unknown algorithm, or one this build lacks the feature for.
+    Algorithm(u8),
+    /// Unknown encoding, or one this build lacks the feature for.
+    Encoding(u8),
+    /// A value no [TOTP] can work with, such as a step of 0 seconds, or a group inside a group.
+    InvalidValue,
+    /// The secret, issuer, account name or an alphabet is longer than a record can hold.
+    TooLong,
+}
+
+impl core::error::Error for BinaryError {}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::Malformed => f.write_str("Not a TOTP record."),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported TOTP record version {}.", version)
+            }
+            BinaryError::Checksum => f.write_str("TOTP record is corrupted."),
+            BinaryError::Algorithm(algorithm) => {
+                write!(f, "Unsupported algorithm {} in TOTP record.", algorithm)
+            }
+            BinaryError::Encoding(encoding) => {
+                write!(f, "Unsupported encoding {} in TOTP record.", encoding)
+            }
+            BinaryError::InvalidValue => f.write_str("TOTP record holds an invalid value."),
+            BinaryError::TooLong => f.write_str("Field too long for a TOTP record."),
+        }
+    }
+}
+
+impl TOTP {
+    /// Will write the TOTP as a compact binary record, see [from_bytes](struct.TOTP.html#method.from_bytes)
+    ///
+    /// The record holds the secret in clear, and is zeroized when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Will return [BinaryError::TooLong] if the secret, issuer, account name or a custom alphabet exceed 255 bytes,
+    /// or the issuer 254, and [BinaryError::InvalidValue] if the encoding has a group inside a group.
+    pub fn to_bytes(&self) -> Result<Zeroizing<Vec<u8>>, BinaryError> {
+        let mut out = Zeroizing::new(Vec::with_capacity(32 + self.secret.len()));
+        out.push(BINARY_VERSION);
+        out.push(algorithm_id(self.algorithm));
+        out.push(u8::try_from(self.digits).map_err(|_| BinaryError::TooLong)?);
+        out.push(self.skew);
+        write_varint(&mut out, self.step);
+        write_varint(&mut out, self.t0);
+        match &self.encoding {
+            Some(encoding) => write_encoding(&mut out, encoding)?,
+            None => out.push(0),
+        }
+        write_bytes(&mut out, self.secret.expose_secret())?;
+        #[cfg(feature = "otpauth")]
+        {
+            match &self.issuer {
+                Some(issuer) if issuer.len() < 255 => {
+                    out.push(issuer.len() as u8 + 1);
+                    out.extend_from_slice(issuer.as_bytes());
+                }
+                Some(_) => return Err(BinaryError::TooLong),
+                None => out.push(0),
+            }
+            write_bytes(&mut out, self.account_name.as_bytes())?;
+        }
+        #[cfg(not(feature = "otpauth"))]
+        out.extend_from_slice(&[0, 0]);
+        let checksum = crc32(&out);
+        out.extend_from_slice(&checksum.to_be_bytes());
+        Ok(out)
+    }
+
+    /// Will read a TOTP back from a record written by [to_bytes](struct.TOTP.html#method.to_bytes)
+    ///
+    /// Like [new_unchecked](struct.TOTP.html#method.new_unchecked), the digits and the size of the secret are not checked.
+    ///
+    /// # Errors
+    ///
+    /// Will return a [BinaryError] if the record is not one, is corrupted, or uses something this build doesn't support.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TOTP, BinaryError> {
+        match bytes.first() {
+            None => return Err(BinaryError::Malformed),
+            Some(&BINARY_VERSION) => {}
+            Some(&version) => return Err(BinaryError::UnsupportedVersion(version)),
+        }
+        if bytes.len() < 1 + CHECKSUM_LEN {
+            return Err(BinaryError::Malformed);
+        }
+        let (record, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if crc32(record).to_be_bytes() != checksum {
+            return Err(BinaryError::Checksum);
+        }
+
+        let mut reader = Reader(&record[1..]);
+        let algorithm = algorithm_from_id(reader.byte()?)?;
+        let digits = reader.byte()? as usize;
+        let skew = reader.byte()?;
+        let step = reader.varint()?;
+        let t0 = reader.varint()?;
+        let encoding = match reader.byte()? {
+            0 => None,
+            id => Some(read_encoding(&mut reader, id)?),
+        };
+        let secret = reader.bytes()?;
+        let issuer = match reader.byte()? {
+            0 => None,
+            len => Some(reader.take(len as usize - 1)?),
+        };
+        let account_name = reader.bytes()?;
+        if !reader.0.is_empty() {
+            return Err(BinaryError::Malformed);
+        }
+        if digits == 0 || step == 0 {
+            return Err(BinaryError::InvalidValue);
+        }
+
+        #[cfg(feature = "otpauth")]
+        let mut totp = {
+            let text = |bytes: &[u8]| {
+                String::from_utf8(bytes.to_vec()).map_err(|_| BinaryError::Malformed)
+            };
+            let issuer = issuer.map(text).transpose()?;
+            let account_name = text(account_name)?;
+            TOTP::new_unchecked(
+                algorithm,
+                digits,
+                skew,
+                step,
+                secret.to_vec(),
+                issuer,
+                account_name,
+            )
+        };
+        #[cfg(not(feature = "otpauth"))]
+        let mut totp = {
+            let _ = (issuer, account_name);
+            TOTP::new_unchecked(algorithm, digits, skew, step, secret.to_vec())
+        };
+        totp.t0 = t0;
+        totp.encoding = encoding;
+        Ok(totp)
+    }
+}
+
+fn algorithm_id(algorithm: Algorithm) -> u8 {
+    match algorithm {
+        Algorithm::SHA1 => 1,
+        Algorithm::SHA224 => 2,
+        Algorithm::SHA256 => 3,
+        Algorithm::SHA384 => 4,
+        Algorithm::SHA512 => 5,
+        Algorithm::SHA512_224 => 6,
+        Algorithm::SHA512_256 => 7,
+        #[cfg(feature = "steam")]
+        Algorithm::Steam => 8,
+    }
+}
+
+fn algorithm_from_id(id: u8) -> Result<Algorithm, BinaryError> {
+    Ok(match id {
+        1 => Algorithm::SHA1,
+        2 => Algorithm::SHA224,
+        3 => Algorithm::SHA256,
+        4 => Algorithm::SHA384,
+        5 => Algorithm::SHA512,
+        6 => Algorithm::SHA512_224,
+        7 => Algorithm::SHA512_256,
+        #[cfg(feature = "steam")]
+        8 => Algorithm::Steam,
+        id => return Err(BinaryError::Algorithm(id)),
+    })
+}
+
+fn write_encoding(out: &mut Vec<u8>, encoding: &Encoding) -> Result<(), BinaryError> {
+    match encoding {
+        Encoding::Decimal => out.push(1),
+        #[cfg(feature = "steam")]
+        Encoding::Steam => out.push(2),
+        Encoding::Custom(symbols) => {
+            out.push(3);
+            write_bytes(out, symbols.as_str().as_bytes())?;
+        }
+        Encoding::Grouped {
+            encoding,
+            size,
+            separator,
+        } => {
+            if let Encoding::Grouped { .. } = **encoding {
+                return Err(BinaryError::InvalidValue);
+            }
+            out.push(4);
+            write_varint(out, *size as u64);
+            let mut buf = [0; 4];
+            write_bytes(out, separator.encode_utf8(&mut buf).as_bytes())?;
+            write_encoding(out, encoding)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_encoding(reader: &mut Reader<'_>, id: u8) -> Result<Encoding, BinaryError> {
+    Ok(match id {
+        1 => Encoding::Decimal,
+        #[cfg(feature = "steam")]
+        2 => Encoding::Steam,
+        3 => {
+            let symbols =
+                core::str::from_utf8(reader.bytes()?).map_err(|_| BinaryError::Malformed)?;
+            Encoding::Custom(Symbols::new(symbols).ok_or(BinaryError::InvalidValue)?)
+        }
+        4 => {
+            let size = usize::try_from(reader.varint()?).map_err(|_| BinaryError::InvalidValue)?;
+            let separator =
+                core::str::from_utf8(reader.bytes()?).map_err(|_| BinaryError::Malformed)?;
+            let mut chars = separator.chars();
+            let separator = match (chars.next(), chars.next()) {
+                (Some(separator), None) => separator,
+                _ => return Err(BinaryError::Malformed),
+            };
+            // Nothing renders differently with nested groups, and reading them would recurse
+            let id = match reader.byte()? {
+                4 => return Err(BinaryError::InvalidValue),
+                id => id,
+            };
+            Encoding::Grouped {
+                encoding: Box::new(read_encoding(reader, id)?),
+                size,
+                separator,
+            }
+        }
+        id => return Err(BinaryError::Encoding(id)),
+    })
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), BinaryError> {
+    out.push(u8::try_from(bytes.len()).map_err(|_| BinaryError::TooLong)?);
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Reads the fields of a record from the front
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        if self.0.len() < len {
+            return Err(BinaryError::Malformed);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], BinaryError> {
+        let len = self.byte()?;
+        self.take(len as usize)
+    }
+
+    fn varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            // Bits past the 64th, or a needlessly long encoding, aren't what to_bytes writes
+            if bits << shift >> shift != bits || (byte == 0 && shift > 0) {
+                return Err(BinaryError::Malformed);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(BinaryError::Malformed)
+    }
+}
+
+/// CRC-32 as in IEEE 802.3, bit by bit as records are small
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    const SECRET: &[u8] = b"TestSecretSuperSecret";
+
+    fn totp() -> TOTP {
+        TOTP::builder().secret(SECRET).build().unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn varints() {
+        for value in [0, 1, 127, 128, 300, 1 << 35, u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            let mut reader = Reader(&out);
+            assert_eq!(reader.varint(), Ok(value));
+            assert!(reader.0.is_empty());
+        }
+        assert_eq!(Reader(&[0x80, 0x00]).varint(), Err(BinaryError::Malformed));
+        assert_eq!(Reader(&[0x80]).varint(), Err(BinaryError::Malformed));
+        assert_eq!(Reader(&[0xff; 10]).varint(), Err(BinaryError::Malformed));
+        assert_eq!(
+            Reader(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]).varint(),
+            Err(BinaryError::Malformed)
+        );
+    }
+
+    #[test]
+    fn format() {
+        // Records outlive builds, this must not change without a new version
+        assert_eq!(
+            hex(&totp().to_bytes().unwrap()),
+            "010106011e0000155465737453656372657453757065725365637265740000e856fe68"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut totps = vec![totp()];
+        let mut totp = TOTP::builder()
+            .algorithm(Algorithm::SHA512)
+            .digits(8)
+            .skew(0)
+            .step(3600)
+            .t0(1_700_000_000)
+            .secret(vec![0xa5; 64])
+            .encoding(Encoding::Custom(Symbols::new("0123456789ABCDEF").unwrap()).grouped(4, '·'))
+            .build()
+            .unwrap();
+        totps.push(totp.clone());
+        totp.encoding = Some(Encoding::Decimal);
+        totps.push(totp);
+        #[cfg(feature = "steam")]
+        totps.push(
+            TOTP::builder()
+                .algorithm(Algorithm::Steam)
+                .digits(5)
+                .secret(SECRET)
+                .build()
+                .unwrap(),
+        );
+
+        for totp in totps {
+            let record = totp.to_bytes().unwrap();
+            let read = TOTP::from_bytes(&record).unwrap();
+            assert_eq!(read, totp);
+            assert_eq!(read.encoding, totp.encoding);
+            assert_eq!(read.generate(1_800_000_000), totp.generate(1_800_000_000));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn round_trips_names() {
+        let mut totp = totp();
+        for issuer in [None, Some(""), Some("Github")] {
+            totp.issuer = issuer.map(String::from);
+            totp.account_name = "constantoine@github.com".into();
+            let read = TOTP::from_bytes(&totp.to_bytes().unwrap()).unwrap();
+            assert_eq!(read.issuer, totp.issuer);
+            assert_eq!(read.account_name, totp.account_name);
+        }
+        totp.issuer = Some("x".repeat(255));
+        assert_eq!(totp.to_bytes().unwrap_err(), BinaryError::TooLong);
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn skips_names() {
+        // As written with feature otpauth, issuer "Git" and account name "me"
+        let mut record = totp().to_bytes().unwrap()[..29].to_vec();
+        record.extend_from_slice(&[4, b'G', b'i', b't', 2, b'm', b'e']);
+        record.extend_from_slice(&crc32(&record).to_be_bytes());
+        assert_eq!(TOTP::from_bytes(&record).unwrap(), totp());
+    }
+
+    #[test]
+    fn rejects_damage() {
+        let record = totp().to_bytes().unwrap();
+        for len in 0..record.len() {
+            assert!(TOTP::from_bytes(&record[..len]).is_err(), "{}", len);
+        }
+        for i in 1..record.len() {
+            let mut damaged = record.to_vec();
+            damaged[i] ^= 0x10;
+            assert_eq!(TOTP::from_bytes(&damaged), Err(BinaryError::Checksum));
+        }
+        let mut longer = record.to_vec();
+        longer.insert(0, 0);
+        assert_eq!(
+            TOTP::from_bytes(&longer),
+            Err(BinaryError::UnsupportedVersion(0))
+        );
+        let mut newer = record.to_vec();
+        newer[0] = 2;
+        assert_eq!(
+            TOTP::from_bytes(&newer),
+            Err(BinaryError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let resealed = |edit: &dyn Fn(&mut Vec<u8>)| {
+            let record = totp().to_bytes().unwrap();
+            let mut record = record[..record.len() - CHECKSUM_LEN].to_vec();
+            edit(&mut record);
+            record.extend_from_slice(&crc32(&record).to_be_bytes());
+            TOTP::from_bytes(&record)
+        };
+        assert_eq!(resealed(&|r| r[1] = 0), Err(BinaryError::Algorithm(0)));
+        assert_eq!(resealed(&|r| r[1] = 9), Err(BinaryError::Algorithm(9)));
+        assert_eq!(resealed(&|r| r[2] = 0), Err(BinaryError::InvalidValue));
+        assert_eq!(resealed(&|r| r[4] = 0), Err(BinaryError::InvalidValue));
+        assert_eq!(resealed(&|r| r[6] = 9), Err(BinaryError::Encoding(9)));
+        assert_eq!(resealed(&|r| r.push(0)), Err(BinaryError::Malformed));
+        assert_eq!(resealed(&|r| r[7] = 0xff), Err(BinaryError::Malformed));
+        assert!(resealed(&|_| {}).is_ok());
+
+        let mut totp = totp();
+        totp.secret = vec![0; 256].into();
+        assert_eq!(totp.to_bytes().unwrap_err(), BinaryError::TooLong);
+    }
+
+    #[test]
+    fn rejects_nested_groups() {
+        let resealed = |encoding: &[u8]| {
+            let record = totp().to_bytes().unwrap();
+            let mut record = record[..record.len() - CHECKSUM_LEN].to_vec();
+            record.splice(6..7, encoding.iter().copied());
+            record.extend_from_slice(&crc32(&record).to_be_bytes());
+            TOTP::from_bytes(&record)
+        };
+        let group = [4, 3, 1, b'-'];
+        let mut grouped = group.to_vec();
+        grouped.push(1);
+        assert_eq!(
+            resealed(&grouped).unwrap().encoding,
+            Some(Encoding::Decimal.grouped(3, '-'))
+        );
+        // Deep enough to overflow the stack if each group were read recursively
+        let mut nested = group.repeat(200_000);
+        nested.push(1);
+        assert_eq!(resealed(&nested), Err(BinaryError::InvalidValue));
+
+        let mut totp = totp();
+        totp.encoding = Some(Encoding::Decimal.grouped(3, ' ').grouped(2, '-'));
+        assert_eq!(totp.to_bytes().unwrap_err(), BinaryError::InvalidValue);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            BinaryError::UnsupportedVersion(2).to_string(),
+            "Unsupported TOTP record version 2."
+        );
+        assert_eq!(
+            BinaryError::Checksum.to_string(),
+            "TOTP record is corrupted."
+        );
+    }
+}
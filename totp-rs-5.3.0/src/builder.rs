@@ -0,0 +1,444 @@
+//! Named, checked construction of a [TOTP] or a [Rfc6238], rather than positional arguments.
+//!
+//! Every option starts at its [rfc-6238](https://tools.ietf.org/html/rfc6238) default, and nothing is
+//! checked until [build](TotpBuilder::build), which reports the first problem as a [BuildError]:
+//!
+//! ```rust
+//! use totp_rs::{Algorithm, BuildError, TOTP};
+//!
+//! let totp = TOTP::builder()
+//!     .algorithm(Algorithm::SHA256)
+//!     .digits(8)
+//!     .secret(b"TestSecretSuperSecret".to_vec())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(totp.step, 30);
+//!
+//! let error = TOTP::builder().digits(8).build().unwrap_err();
+//! assert_eq!(error, BuildError::MissingSecret);
+//! ```
+
+#[cfg(feature = "otpauth")]
+use alloc::string::String;
+use core::fmt;
+
+use crate::{Algorithm, Encoding, Rfc6238, SecretBytes, TOTP};
+
+/// Options of a [TOTP] or [Rfc6238] being built, see [TOTP::builder]
+///
+/// Defaults to SHA-1, 6 digits, a skew of 1, 30 second steps from the Unix epoch, and no secret.
+#[derive(Debug, Clone)]
+pub struct TotpBuilder {
+    algorithm: Algorithm,
+    digits: usize,
+    skew: u8,
+    step: u64,
+    t0: u64,
+    secret: Option<SecretBytes>,
+    encoding: Option<Encoding>,
+    #[cfg(feature = "otpauth")]
+    issuer: Option<String>,
+    #[cfg(feature = "otpauth")]
+    account_name: String,
+}
+
+impl Default for TotpBuilder {
+    fn default() -> Self {
+        TotpBuilder {
+            algorithm: Algorithm::SHA1,
+            digits: 6,
+            skew: 1,
+            step: 30,
+            t0: 0,
+            secret: None,
+            encoding: None,
+            #[cfg(feature = "otpauth")]
+            issuer: None,
+            #[cfg(feature = "otpauth")]
+            account_name: String::new(),
+        }
+    }
+}
+
+impl TOTP {
+    /// Will start building a TOTP from the [rfc-6238](https://tools.ietf.org/html/rfc6238) defaults
+    pub fn builder() -> TotpBuilder {
+        TotpBuilder::default()
+    }
+}
+
+impl Rfc6238 {
+    /// Will start building a set of [rfc-6238](https://tools.ietf.org/html/rfc6238) options, see [build_rfc6238](TotpBuilder::build_rfc6238)
+    pub fn builder() -> TotpBuilder {
+        TotpBuilder::default()
+    }
+}
+
+impl TotpBuilder {
+    /// Set the HMAC [algorithm](struct.TOTP.html#structfield.algorithm), SHA-1 by default.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the number of [digits](struct.TOTP.html#structfield.digits), 6 by default.
+    pub fn digits(mut self, digits: usize) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    /// Set the [skew](struct.TOTP.html#structfield.skew), 1 by default.
+    pub fn skew(mut self, skew: u8) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// Set the [step](struct.TOTP.html#structfield.step) in seconds, 30 by default.
+    pub fn step(mut self, step: u64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Set the Unix time [t0](struct.TOTP.html#structfield.t0) at which step 0 starts, 0 by default.
+    pub fn t0(mut self, t0: u64) -> Self {
+        self.t0 = t0;
+        self
+    }
+
+    /// Set the non-encoded [secret](struct.TOTP.html#structfield.secret), which has no default.
+    pub fn secret(mut self, secret: impl Into<SecretBytes>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Set the [encoding](struct.TOTP.html#structfield.encoding) of the tokens, the default of the algorithm otherwise.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Set the [issuer](struct.TOTP.html#structfield.issuer), none by default.
+    #[cfg(feature = "otpauth")]
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Set the [account_name](struct.TOTP.html#structfield.account_name), the label of the URL, empty by default.
+    #[cfg(feature = "otpauth")]
+    #[doc(alias = "label")]
+    pub fn account_name(mut self, account_name: impl Into<String>) -> Self {
+        self.account_name = account_name.into();
+        self
+    }
+
+    /// Will check the options and create the [TOTP]
+    ///
+    /// # Errors
+    ///
+    /// Will return a [BuildError] when
+    /// - there is no secret, or it is smaller than 128 bits.
+    /// - decimal tokens would not have 6 to 8 digits, or other tokens none at all.
+    /// - `step` is 0.
+    /// - `issuer` or `account_name` contain the character `:`.
+    pub fn build(mut self) -> Result<TOTP, BuildError> {
+        if self.is_decimal() {
+            check_digits(self.digits)?;
+        } else if self.digits == 0 {
+            return Err(BuildError::InvalidDigits(0));
+        }
+        let secret = self.check()?;
+
+        Ok(TOTP {
+            algorithm: self.algorithm,
+            digits: self.digits,
+            skew: self.skew,
+            step: self.step,
+            secret,
+            t0: self.t0,
+            encoding: self.encoding,
+            #[cfg(feature = "otpauth")]
+            issuer: self.issuer,
+            #[cfg(feature = "otpauth")]
+            account_name: self.account_name,
+        })
+    }
+
+    /// Will check the options and create the [Rfc6238], whose tokens are always decimal
+    ///
+    /// # Errors
+    ///
+    /// Same as [build](TotpBuilder::build), and [BuildError::NotDecimal] if the tokens wouldn't be decimal.
+    pub fn build_rfc6238(mut self) -> Result<Rfc6238, BuildError> {
+        if !self.is_decimal() {
+            return Err(BuildError::NotDecimal);
+        }
+        check_digits(self.digits)?;
+        let secret = self.check()?;
+
+        Ok(Rfc6238 {
+            algorithm: self.algorithm,
+            digits: self.digits,
+            skew: self.skew,
+            step: self.step,
+            secret,
+            t0: self.t0,
+            #[cfg(feature = "otpauth")]
+            issuer: self.issuer,
+            #[cfg(feature = "otpauth")]
+            account_name: self.account_name,
+        })
+    }
+
+    fn is_decimal(&self) -> bool {
+        match &self.encoding {
+            Some(encoding) => encoding.is_decimal(),
+            None => self.algorithm.default_encoding().is_decimal(),
+        }
+    }
+
+    /// Checks the options shared by both, handing over the secret
+    fn check(&mut self) -> Result<SecretBytes, BuildError> {
+        if self.step == 0 {
+            return Err(BuildError::ZeroStep);
+        }
+        #[cfg(feature = "otpauth")]
+        {
+            if let Some(issuer) = self.issuer.as_ref().filter(|issuer| issuer.contains(':')) {
+                return Err(BuildError::Issuer(issuer.clone()));
+            }
+            if self.account_name.contains(':') {
+                return Err(BuildError::AccountName(self.account_name.clone()));
+            }
+        }
+        let secret = self.secret.take().ok_or(BuildError::MissingSecret)?;
+        if secret.len() < 16 {
+            return Err(BuildError::SecretTooSmall(secret.len() * 8));
+        }
+        Ok(secret)
+    }
+}
+
+fn check_digits(digits: usize) -> Result<(), BuildError> {
+    if (6..=8).contains(&digits) {
+        Ok(())
+    } else {
+        Err(BuildError::InvalidDigits(digits))
+    }
+}
+
+/// Why a [TotpBuilder] could not build
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BuildError {
+    /// No secret was given.
+    MissingSecret,
+    /// The secret has fewer than 128 bits, this many.
+    SecretTooSmall(usize),
+    /// Decimal tokens must have 6 to 8 digits, others at least 1.
+    InvalidDigits(usize),
+    /// A step of 0 seconds would never end.
+    ZeroStep,
+    /// An [Rfc6238] only produces decimal tokens.
+    NotDecimal,
+    /// Issuer contains invalid character `:`.
+    #[cfg(feature = "otpauth")]
+    Issuer(String),
+    /// Account name contains invalid character `:`.
+    #[cfg(feature = "otpauth")]
+    AccountName(String),
+}
+
+impl core::error::Error for BuildError {}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingSecret => f.write_str("A secret is required."),
+            BuildError::SecretTooSmall(bits) => write!(
+                f,
+                "The length of the shared secret MUST be at least 128 bits. {} bits is not enough",
+                bits,
+            ),
+            BuildError::InvalidDigits(digits) => write!(
+                f,
+                "Decimal tokens need 6 to 8 digits, others at least 1. {} digits is not allowed",
+                digits,
+            ),
+            BuildError::ZeroStep => f.write_str("Step must be at least 1 second."),
+            BuildError::NotDecimal => f.write_str("RFC 6238 tokens can only be decimal."),
+            #[cfg(feature = "otpauth")]
+            BuildError::Issuer(issuer) => write!(
+                f,
+                "Issuer can't contain a colon. \"{}\" contains a colon",
+                issuer
+            ),
+            #[cfg(feature = "otpauth")]
+            BuildError::AccountName(name) => write!(
+                f,
+                "Account Name can't contain a colon. \"{}\" contains a colon",
+                name
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    const SECRET: &[u8] = b"TestSecretSuperSecret";
+
+    #[test]
+    fn defaults() {
+        let totp = TOTP::builder().secret(SECRET).build().unwrap();
+        assert_eq!(totp.algorithm, Algorithm::SHA1);
+        assert_eq!(totp.digits, 6);
+        assert_eq!(totp.skew, 1);
+        assert_eq!(totp.step, 30);
+        assert_eq!(totp.t0, 0);
+        assert_eq!(totp.encoding, None);
+        assert_eq!(totp.secret.expose_secret(), SECRET);
+        let rfc = Rfc6238::builder().secret(SECRET).build_rfc6238().unwrap();
+        assert_eq!(TOTP::try_from(rfc).unwrap(), totp);
+    }
+
+    #[test]
+    fn every_option() {
+        let totp = TOTP::builder()
+            .algorithm(Algorithm::SHA512)
+            .digits(8)
+            .skew(2)
+            .step(60)
+            .t0(1000)
+            .secret(SECRET)
+            .encoding(Encoding::Decimal.grouped(4, ' '))
+            .build()
+            .unwrap();
+        assert_eq!(totp.algorithm, Algorithm::SHA512);
+        assert_eq!(totp.digits, 8);
+        assert_eq!(totp.skew, 2);
+        assert_eq!(totp.step, 60);
+        assert_eq!(totp.t0, 1000);
+        assert_eq!(totp.generate(1059).len(), 9);
+    }
+
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn otpauth_options() {
+        let totp = TOTP::builder()
+            .secret(SECRET)
+            .issuer("Github")
+            .account_name("constantoine@github.com")
+            .build()
+            .unwrap();
+        assert_eq!(totp.issuer.as_deref(), Some("Github"));
+        assert_eq!(totp.account_name, "constantoine@github.com");
+        let rfc = Rfc6238::builder()
+            .secret(SECRET)
+            .issuer("Github")
+            .account_name("constantoine@github.com")
+            .build_rfc6238()
+            .unwrap();
+        assert_eq!(
+            TOTP::try_from(rfc).unwrap().issuer.as_deref(),
+            Some("Github")
+        );
+
+        let builder = TOTP::builder().secret(SECRET);
+        assert_eq!(
+            builder.clone().issuer("Git:hub").build(),
+            Err(BuildError::Issuer("Git:hub".into()))
+        );
+        assert_eq!(
+            builder.account_name(":me").build_rfc6238().unwrap_err(),
+            BuildError::AccountName(":me".into())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let builder = TOTP::builder().secret(SECRET);
+        assert_eq!(
+            TOTP::builder().build().unwrap_err(),
+            BuildError::MissingSecret
+        );
+        assert_eq!(
+            TOTP::builder().secret(&SECRET[..15]).build().unwrap_err(),
+            BuildError::SecretTooSmall(120)
+        );
+        for digits in [0, 5, 9] {
+            assert_eq!(
+                builder.clone().digits(digits).build().unwrap_err(),
+                BuildError::InvalidDigits(digits)
+            );
+            assert_eq!(
+                builder.clone().digits(digits).build_rfc6238().unwrap_err(),
+                BuildError::InvalidDigits(digits)
+            );
+        }
+        assert_eq!(
+            builder.clone().step(0).build().unwrap_err(),
+            BuildError::ZeroStep
+        );
+
+        let hex = Encoding::Custom(crate::Symbols::new("0123456789abcdef").unwrap());
+        assert!(builder
+            .clone()
+            .encoding(hex.clone())
+            .digits(4)
+            .build()
+            .is_ok());
+        assert_eq!(
+            builder
+                .clone()
+                .encoding(hex.clone())
+                .digits(0)
+                .build()
+                .unwrap_err(),
+            BuildError::InvalidDigits(0)
+        );
+        assert_eq!(
+            builder.encoding(hex).build_rfc6238().unwrap_err(),
+            BuildError::NotDecimal
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "steam")]
+    fn steam() {
+        let totp = TOTP::builder()
+            .algorithm(Algorithm::Steam)
+            .digits(5)
+            .secret(SECRET)
+            .build()
+            .unwrap();
+        #[cfg(not(feature = "otpauth"))]
+        assert_eq!(totp, TOTP::new_steam(SECRET.into()));
+        assert_eq!(totp.generate(1000), "RBJNV");
+        assert_eq!(
+            Rfc6238::builder()
+                .algorithm(Algorithm::Steam)
+                .secret(SECRET)
+                .build_rfc6238()
+                .unwrap_err(),
+            BuildError::NotDecimal
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            BuildError::MissingSecret.to_string(),
+            "A secret is required."
+        );
+        assert_eq!(
+            BuildError::InvalidDigits(9).to_string(),
+            "Decimal tokens need 6 to 8 digits, others at least 1. 9 digits is not allowed"
+        );
+        assert_eq!(
+            BuildError::ZeroStep.to_string(),
+            "Step must be at least 1 second."
+        );
+    }
+}
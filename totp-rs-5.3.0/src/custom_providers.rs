@@ -1,5 +1,9 @@
 #[cfg(feature = "steam")]
 use crate::{Algorithm, TOTP};
+#[cfg(all(feature = "steam", feature = "otpauth"))]
+use alloc::string::String;
+#[cfg(feature = "steam")]
+use alloc::vec::Vec;
 
 #[cfg(feature = "steam")]
 impl TOTP {
@@ -7,7 +11,7 @@ impl TOTP {
     /// Will create a new instance of TOTP using the Steam algorithm with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values
     ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP};
@@ -30,11 +34,11 @@ impl TOTP {
     /// Will create a new instance of TOTP using the Steam algorithm with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values
     ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP};
-    /// let secret = Secret::Encoded("ABCDEFGHIJKLMNOPQRSTUVWXYZ234567".to_string());
+    /// let secret = Secret::Encoded("ABCDEFGHIJKLMNOPQRSTUVWXYZ234567".into());
     /// let totp = TOTP::new_steam(secret.to_bytes().unwrap());
     /// ```
     pub fn new_steam(secret: Vec<u8>) -> TOTP {
@@ -44,7 +48,6 @@ impl TOTP {
 
 #[cfg(all(test, feature = "steam"))]
 mod test {
-    #[cfg(feature = "otpauth")]
     use super::*;
 
     #[test]
@@ -54,4 +57,13 @@ mod test {
         let url = totp.get_url();
         assert_eq!(url.as_str(), "otpauth://steam/Steam:constantoine?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&digits=5&algorithm=SHA1&issuer=Steam");
     }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn generate_steam() {
+        let totp = TOTP::new_steam("TestSecretSuperSecret".into());
+        assert_eq!(totp.generate(1000), "RBJNV");
+        let mut buf = [0; 5];
+        assert_eq!(totp.generate_into(1234567890, &mut buf), Some("MCX46"));
+    }
 }
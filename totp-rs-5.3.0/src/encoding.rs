@@ -0,0 +1,366 @@
+//! How the truncated HMAC of a time step is rendered as the token the user types.
+//!
+//! The [Algorithm](crate::Algorithm) only decides which HMAC is computed. A [TOTP](crate::TOTP)
+//! renders it in decimal unless its [encoding](crate::TOTP::encoding) says otherwise:
+//!
+//! ```rust
+//! use totp_rs::{Encoding, Symbols, TOTP};
+//!
+//! let mut totp = TOTP::builder().secret(b"TestSecretSuperSecret".to_vec()).build().unwrap();
+//! assert_eq!(totp.generate(1000), "804420");
+//! totp.encoding = Some(Encoding::Decimal.grouped(3, ' '));
+//! assert_eq!(totp.generate(1000), "804 420");
+//! assert!(totp.check("804420", 1000));
+//! totp.encoding = Some(Encoding::Custom(Symbols::new("ABCDEFGHJK").unwrap()));
+//! assert_eq!(totp.generate(1000), "JAEECA");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+/// Alphabet for Steam tokens.
+#[cfg(feature = "steam")]
+const STEAM_CHARS: &str = "23456789BCDFGHJKMNPQRTVWXY";
+
+const DECIMAL_CHARS: &str = "0123456789";
+
+/// Output format of the tokens, independent of the [Algorithm](crate::Algorithm)
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum Encoding {
+    /// Digits `0`-`9`, as per [rfc-4226](https://tools.ietf.org/html/rfc4226#section-5.3)
+    Decimal,
+    /// Steam Guard's 26 characters, least significant first
+    #[cfg(feature = "steam")]
+    Steam,
+    /// Characters of the given alphabet, most significant first like decimal
+    Custom(Symbols),
+    /// Another encoding with `separator` inserted every `size` characters, counting from the start
+    ///
+    /// Tokens are accepted with or without the separators.
+    Grouped {
+        encoding: Box<Encoding>,
+        size: usize,
+        separator: char,
+    },
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::Decimal
+    }
+}
+
+impl Encoding {
+    /// Will insert `separator` every `size` characters of this encoding, like `123 456`
+    pub fn grouped(self, size: usize, separator: char) -> Encoding {
+        Encoding::Grouped {
+            encoding: Box::new(self),
+            size,
+            separator,
+        }
+    }
+
+    /// Length in bytes of a token of `digits` characters
+    pub fn len(&self, digits: usize) -> usize {
+        match self {
+            Encoding::Grouped {
+                encoding,
+                size,
+                separator,
+            } => {
+                let len = encoding.len(digits);
+                len + separators(len, *size) * separator.len_utf8()
+            }
+            _ => digits,
+        }
+    }
+
+    /// Whether tokens are made of decimal digits, grouped or not
+    pub(crate) fn is_decimal(&self) -> bool {
+        match self {
+            Encoding::Decimal => true,
+            Encoding::Grouped { encoding, .. } => encoding.is_decimal(),
+            _ => false,
+        }
+    }
+
+    /// Will reduce a truncated HMAC value to the number a token of `digits` characters represents
+    ///
+    /// Steam tokens ignore the most significant part of the value rather than reducing it.
+    pub(crate) fn reduce(&self, value: u32, digits: usize) -> u32 {
+        let radix = match self {
+            #[cfg(feature = "steam")]
+            Encoding::Steam => return value,
+            Encoding::Grouped { encoding, .. } => return encoding.reduce(value, digits),
+            Encoding::Decimal => DECIMAL_CHARS.len() as u32,
+            Encoding::Custom(symbols) => symbols.0.len() as u32,
+        };
+        match radix.checked_pow(digits as u32) {
+            Some(modulus) => value % modulus,
+            None => value,
+        }
+    }
+
+    /// Calls `out` with the position and byte of every character of the token for a truncated HMAC value
+    pub(crate) fn render(&self, value: u32, digits: usize, out: &mut dyn FnMut(usize, u8)) {
+        match self {
+            Encoding::Decimal => most_significant_first(DECIMAL_CHARS, value, digits, out),
+            Encoding::Custom(symbols) => most_significant_first(&symbols.0, value, digits, out),
+            #[cfg(feature = "steam")]
+            Encoding::Steam => {
+                let mut value = value;
+                for i in 0..digits {
+                    out(
+                        i,
+                        STEAM_CHARS.as_bytes()[value as usize % STEAM_CHARS.len()],
+                    );
+                    value /= STEAM_CHARS.len() as u32;
+                }
+            }
+            Encoding::Grouped {
+                encoding,
+                size,
+                separator,
+            } => {
+                let mut buf = [0; 4];
+                let separator = separator.encode_utf8(&mut buf).as_bytes();
+                let position = |i: usize| i + separators(i + 1, *size) * separator.len();
+                encoding.render(value, digits, &mut |i, c| {
+                    let at = position(i);
+                    if *size > 0 && i > 0 && i % size == 0 {
+                        for (j, s) in separator.iter().enumerate() {
+                            out(at - separator.len() + j, *s);
+                        }
+                    }
+                    out(at, c);
+                });
+            }
+        }
+    }
+
+    /// Compares a token to the one for a truncated HMAC value, in constant time
+    ///
+    /// Grouped tokens match both with and without their separators.
+    pub(crate) fn matches(&self, value: u32, digits: usize, token: &str) -> bool {
+        let mut ungrouped = self;
+        while let Encoding::Grouped { encoding, .. } = ungrouped {
+            ungrouped = encoding;
+        }
+        let exact = self.diff(value, digits, token.as_bytes());
+        let bare = ungrouped.diff(value, digits, token.as_bytes());
+        (exact == 0) | (bare == 0)
+    }
+
+    fn diff(&self, value: u32, digits: usize, token: &[u8]) -> u8 {
+        let mut diff = (token.len() != self.len(digits)) as u8;
+        self.render(value, digits, &mut |i, c| {
+            diff |= token.get(i).map_or(1, |t| t ^ c)
+        });
+        diff
+    }
+}
+
+/// Number of separators in a grouped token of `len` characters
+fn separators(len: usize, size: usize) -> usize {
+    match size {
+        0 => 0,
+        size => len.saturating_sub(1) / size,
+    }
+}
+
+fn most_significant_first(
+    symbols: &str,
+    mut value: u32,
+    digits: usize,
+    out: &mut dyn FnMut(usize, u8),
+) {
+    let symbols = symbols.as_bytes();
+    for i in (0..digits).rev() {
+        out(i, symbols[value as usize % symbols.len()]);
+        value /= symbols.len() as u32;
+    }
+}
+
+/// Alphabet of a [Encoding::Custom], at least 2 distinct ASCII characters
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(try_from = "String", into = "String"))]
+pub struct Symbols(String);
+
+impl Symbols {
+    /// Will check that `symbols` can be used as an alphabet
+    ///
+    /// Returns `None` if there are fewer than 2 characters, or some of them are repeated or not ASCII.
+    pub fn new(symbols: &str) -> Option<Symbols> {
+        let bytes = symbols.as_bytes();
+        let valid = bytes.len() >= 2
+            && bytes.is_ascii()
+            && bytes
+                .iter()
+                .enumerate()
+                .all(|(i, c)| !bytes[..i].contains(c));
+        if valid {
+            Some(Symbols(symbols.into()))
+        } else {
+            None
+        }
+    }
+
+    /// The characters, from the one for 0 onwards
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Symbols {
+    type Error = InvalidSymbols;
+
+    fn try_from(symbols: String) -> Result<Self, Self::Error> {
+        Symbols::new(&symbols).ok_or(InvalidSymbols)
+    }
+}
+
+impl From<Symbols> for String {
+    fn from(symbols: Symbols) -> Self {
+        symbols.0
+    }
+}
+
+/// The characters given to [Symbols] can't be used as an alphabet
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidSymbols;
+
+impl fmt::Display for InvalidSymbols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("An alphabet needs at least 2 distinct ASCII characters.")
+    }
+}
+
+impl core::error::Error for InvalidSymbols {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    fn render(encoding: &Encoding, value: u32, digits: usize) -> String {
+        let mut token = vec![0; encoding.len(digits)];
+        encoding.render(value, digits, &mut |i, c| token[i] = c);
+        String::from_utf8(token).unwrap()
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(render(&Encoding::Decimal, 1234567, 6), "234567");
+        assert_eq!(render(&Encoding::Decimal, 42, 8), "00000042");
+        assert_eq!(Encoding::Decimal.reduce(1234567, 6), 234567);
+        assert_eq!(Encoding::Decimal.reduce(1234567, 10), 1234567);
+    }
+
+    #[test]
+    fn custom() {
+        let hex = Encoding::Custom(Symbols::new("0123456789abcdef").unwrap());
+        assert_eq!(render(&hex, 0xbeef42, 4), "ef42");
+        assert_eq!(hex.reduce(0xbeef42, 4), 0xef42);
+        assert_eq!(Symbols::new("0"), None);
+        assert_eq!(Symbols::new("0120"), None);
+        assert_eq!(Symbols::new("01é"), None);
+        assert_eq!(
+            Symbols::try_from(String::from("01")).unwrap().as_str(),
+            "01"
+        );
+        assert_eq!(
+            InvalidSymbols.to_string(),
+            "An alphabet needs at least 2 distinct ASCII characters."
+        );
+    }
+
+    #[test]
+    fn grouped() {
+        let grouped = Encoding::Decimal.grouped(3, ' ');
+        assert_eq!(grouped.len(6), 7);
+        assert_eq!(render(&grouped, 123456, 6), "123 456");
+        assert_eq!(render(&grouped, 1234567, 7), "123 456 7");
+        assert_eq!(render(&grouped, 12345678, 8), "123 456 78");
+        assert_eq!(
+            render(&Encoding::Decimal.grouped(4, '-'), 12345678, 8),
+            "1234-5678"
+        );
+        assert_eq!(render(&Encoding::Decimal.grouped(2, '·'), 1234, 4), "12·34");
+        assert_eq!(render(&Encoding::Decimal.grouped(0, ' '), 1234, 4), "1234");
+        assert_eq!(grouped.reduce(1234567, 6), 234567);
+
+        assert!(grouped.matches(123456, 6, "123 456"));
+        assert!(grouped.matches(123456, 6, "123456"));
+        assert!(!grouped.matches(123456, 6, "12 3456"));
+        assert!(!grouped.matches(123456, 6, "123 457"));
+        assert!(!grouped.matches(123456, 6, "123 4567"));
+        assert!(Encoding::Decimal.matches(123456, 6, "123456"));
+        assert!(!Encoding::Decimal.matches(123456, 6, "123 456"));
+    }
+
+    #[test]
+    fn independent_of_algorithm() {
+        use crate::{Algorithm, TOTP};
+
+        let secret = b"12345678901234567890123456789012".to_vec();
+        let mut totp = TOTP::builder()
+            .algorithm(Algorithm::SHA256)
+            .digits(8)
+            .secret(secret)
+            .build()
+            .unwrap();
+        assert_eq!(totp.generate(59), "46119246");
+        let decimal = totp.clone();
+        totp.encoding = Some(Encoding::Custom(Symbols::new("0123456789").unwrap()));
+        assert_eq!(totp.generate(59), "46119246");
+        totp.encoding = Some(Encoding::Decimal.grouped(4, ' '));
+        assert_eq!(totp.generate(59), "4611 9246");
+        assert_eq!(totp.generate_u32(59), 46119246);
+        assert!(totp.check("4611 9246", 89));
+        assert!(totp.check("46119246", 89));
+        assert_ne!(totp, decimal);
+        let mut buf = [0; 8];
+        assert_eq!(totp.generate_into(59, &mut buf), None);
+    }
+
+    #[test]
+    #[cfg(feature = "steam")]
+    fn steam_tokens_from_any_algorithm() {
+        use crate::{Algorithm, TOTP};
+
+        let steam = TOTP::builder()
+            .algorithm(Algorithm::Steam)
+            .digits(5)
+            .secret(b"TestSecretSuperSecret".to_vec())
+            .build()
+            .unwrap();
+        assert_eq!(*steam.encoding(), Encoding::Steam);
+        let sha1 = TOTP::builder()
+            .digits(5)
+            .encoding(Encoding::Steam)
+            .secret(b"TestSecretSuperSecret".to_vec())
+            .build()
+            .unwrap();
+        assert_eq!(sha1.generate(1000), "RBJNV");
+        assert_eq!(sha1.generate(1000), steam.generate(1000));
+        let mut decimal = steam.clone();
+        decimal.encoding = Some(Encoding::Decimal);
+        assert_eq!(decimal.generate(1000).len(), 5);
+        assert!(decimal.generate(1000).bytes().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    #[cfg(feature = "steam")]
+    fn steam() {
+        assert_eq!(render(&Encoding::Steam, 0, 5), "22222");
+        assert_eq!(render(&Encoding::Steam, 27, 5), "33222");
+        assert_eq!(Encoding::Steam.reduce(u32::MAX, 5), u32::MAX);
+        assert_eq!(render(&Encoding::Steam.grouped(2, ' '), 27, 5), "33 22 2");
+    }
+}
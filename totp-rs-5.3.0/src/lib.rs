@@ -9,8 +9,7 @@
 //! # Examples
 //!
 //! ```rust
-//! # #[cfg(feature = "otpauth")] {
-//! use core::time::SystemTime;
+//! # #[cfg(all(feature = "otpauth", feature = "std"))] {
 //! use totp_rs::{Algorithm, TOTP, Secret};
 //!
 //! let totp = TOTP::new(
@@ -18,7 +17,7 @@
 //!     6,
 //!     1,
 //!     30,
-//!     Secret::Raw("TestSecretSuperSecret".as_bytes().to_vec()).to_bytes().unwrap(),
+//!     Secret::Raw("TestSecretSuperSecret".as_bytes().into()).to_bytes().unwrap(),
 //!     Some("Github".to_string()),
 //!     "constantoine@github.com".to_string(),
 //! ).unwrap();
@@ -50,52 +49,91 @@
 #![no_std]
 #![feature(error_in_core)]    // enables core::error::Error
 extern crate alloc;
+#[cfg(any(test, feature = "std"))]
+#[macro_use]
+extern crate std;
 
+mod binary;
+mod builder;
 mod custom_providers;
+mod encoding;
+#[cfg(feature = "otpauth")]
+mod otpauth;
+mod prepared;
 mod rfc;
+mod rotation;
 mod secret;
+mod secret_bytes;
+mod time;
 mod url_error;
+mod verifier;
 
 #[cfg(feature = "qr")]
 pub use qrcodegen_image;
 
+pub use base32::Alphabet as Base32Alphabet;
+pub use binary::{BinaryError, BINARY_VERSION};
+pub use builder::{BuildError, TotpBuilder};
+pub use encoding::{Encoding, InvalidSymbols, Symbols};
+pub use prepared::PreparedTotp;
 pub use rfc::{Rfc6238, Rfc6238Error};
+pub use rotation::{Key, KeyMatch, RotatingTotp, SecondaryKey};
 pub use secret::{Secret, SecretParseError};
+pub use secret_bytes::{Reveal, SecretBytes, SecretString};
+#[cfg(feature = "std")]
+pub use time::SystemTimeSource;
+pub use time::{FixedTime, TimeSource};
 pub use url_error::TotpUrlError;
-
-use constant_time_eq::constant_time_eq;
+#[cfg(feature = "otpauth")]
+pub use url_error::UrlParseError;
+pub use verifier::{Verifier, VerifierState, VerifyError};
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
+use alloc::borrow::Cow;
 use alloc::string::String;
 use alloc::vec::Vec;
+#[cfg(feature = "otpauth")]
 use alloc::format;
-use core::fmt;
-
 #[cfg(feature = "otpauth")]
-use url::{Host, Url};
+use alloc::{borrow::ToOwned, string::ToString};
+use core::fmt;
 
 use hmac::Mac;
 
 #[cfg(feature = "std")]
-use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use std::time::SystemTimeError;
 
 type HmacSha1 = hmac::Hmac<sha1::Sha1>;
+type HmacSha224 = hmac::Hmac<sha2::Sha224>;
 type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+type HmacSha384 = hmac::Hmac<sha2::Sha384>;
 type HmacSha512 = hmac::Hmac<sha2::Sha512>;
+type HmacSha512_224 = hmac::Hmac<sha2::Sha512_224>;
+type HmacSha512_256 = hmac::Hmac<sha2::Sha512_256>;
 
-/// Alphabet for Steam tokens.
-#[cfg(feature = "steam")]
-const STEAM_CHARS: &str = "23456789BCDFGHJKMNPQRTVWXY";
-
-/// Algorithm enum holds the three standards algorithms for TOTP as per the [reference implementation](https://tools.ietf.org/html/rfc6238#appendix-A)
+/// Algorithm enum holds the three standards algorithms for TOTP as per the [reference implementation](https://tools.ietf.org/html/rfc6238#appendix-A),
+/// and the other SHA-2 variants some servers issue tokens with
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub enum Algorithm {
     SHA1,
+    SHA224,
     SHA256,
+    SHA384,
     SHA512,
+    /// SHA-512 truncated to 224 bits
+    ///
+    /// Written as `SHA512_224` in otpauth URLs, a name this crate made up: the Key URI
+    /// format has none for it, so other apps will likely reject such a URL
+    SHA512_224,
+    /// SHA-512 truncated to 256 bits
+    ///
+    /// Written as `SHA512_256` in otpauth URLs, a name this crate made up: the Key URI
+    /// format has none for it, so other apps will likely reject such a URL
+    SHA512_256,
+    /// SHA-1, with tokens in [Encoding::Steam] unless the [TOTP] says otherwise
     #[cfg(feature = "steam")]
     Steam,
 }
@@ -110,8 +148,12 @@ impl fmt::Display for Algorithm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Algorithm::SHA1 => f.write_str("SHA1"),
+            Algorithm::SHA224 => f.write_str("SHA224"),
             Algorithm::SHA256 => f.write_str("SHA256"),
+            Algorithm::SHA384 => f.write_str("SHA384"),
             Algorithm::SHA512 => f.write_str("SHA512"),
+            Algorithm::SHA512_224 => f.write_str("SHA512_224"),
+            Algorithm::SHA512_256 => f.write_str("SHA512_256"),
             #[cfg(feature = "steam")]
             Algorithm::Steam => f.write_str("SHA1"),
         }
@@ -119,32 +161,98 @@ impl fmt::Display for Algorithm {
 }
 
 impl Algorithm {
-    fn hash<D>(mut digest: D, data: &[u8]) -> Vec<u8>
+    /// The encoding tokens are rendered in when a [TOTP] doesn't specify one
+    pub fn default_encoding(&self) -> Encoding {
+        match self {
+            #[cfg(feature = "steam")]
+            Algorithm::Steam => Encoding::Steam,
+            _ => Encoding::Decimal,
+        }
+    }
+
+    fn signer(&self, key: &[u8]) -> Signer {
+        match self {
+            Algorithm::SHA1 => Signer::Sha1(HmacSha1::new_from_slice(key).unwrap()),
+            Algorithm::SHA224 => Signer::Sha224(HmacSha224::new_from_slice(key).unwrap()),
+            Algorithm::SHA256 => Signer::Sha256(HmacSha256::new_from_slice(key).unwrap()),
+            Algorithm::SHA384 => Signer::Sha384(HmacSha384::new_from_slice(key).unwrap()),
+            Algorithm::SHA512 => Signer::Sha512(HmacSha512::new_from_slice(key).unwrap()),
+            Algorithm::SHA512_224 => {
+                Signer::Sha512_224(HmacSha512_224::new_from_slice(key).unwrap())
+            }
+            Algorithm::SHA512_256 => {
+                Signer::Sha512_256(HmacSha512_256::new_from_slice(key).unwrap())
+            }
+            #[cfg(feature = "steam")]
+            Algorithm::Steam => Signer::Sha1(HmacSha1::new_from_slice(key).unwrap()),
+        }
+    }
+}
+
+/// An HMAC already keyed with the secret, cloned for every counter so the key is only processed once
+#[derive(Clone)]
+enum Signer {
+    Sha1(HmacSha1),
+    Sha224(HmacSha224),
+    Sha256(HmacSha256),
+    Sha384(HmacSha384),
+    Sha512(HmacSha512),
+    Sha512_224(HmacSha512_224),
+    Sha512_256(HmacSha512_256),
+}
+
+impl Signer {
+    fn hash<D, R>(digest: &D, counter: u64, f: impl FnOnce(&[u8]) -> R) -> R
     where
-        D: Mac,
+        D: Mac + Clone,
     {
-        digest.update(data);
-        digest.finalize().into_bytes().to_vec()
+        let mut digest = digest.clone();
+        digest.update(&counter.to_be_bytes());
+        #[allow(unused_mut)]
+        let mut hash = digest.finalize().into_bytes();
+        let result = f(&hash);
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(hash.as_mut_slice());
+        result
     }
 
-    fn sign(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+    /// Calls `f` with the HMAC of the counter, which never leaves the stack
+    fn sign<R>(&self, counter: u64, f: impl FnOnce(&[u8]) -> R) -> R {
         match self {
-            Algorithm::SHA1 => Algorithm::hash(HmacSha1::new_from_slice(key).unwrap(), data),
-            Algorithm::SHA256 => Algorithm::hash(HmacSha256::new_from_slice(key).unwrap(), data),
-            Algorithm::SHA512 => Algorithm::hash(HmacSha512::new_from_slice(key).unwrap(), data),
-            #[cfg(feature = "steam")]
-            Algorithm::Steam => Algorithm::hash(HmacSha1::new_from_slice(key).unwrap(), data),
+            Signer::Sha1(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha224(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha256(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha384(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha512(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha512_224(digest) => Signer::hash(digest, counter, f),
+            Signer::Sha512_256(digest) => Signer::hash(digest, counter, f),
         }
     }
+
+    /// Dynamic truncation of the HMAC of the counter, as per [RFC 4226](https://tools.ietf.org/html/rfc4226#section-5.3)
+    fn truncate(&self, counter: u64) -> u32 {
+        self.sign(counter, |hash| {
+            let offset = (hash[hash.len() - 1] & 15) as usize;
+            u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff
+        })
+    }
 }
 
-#[cfg(feature = "std")]
-fn system_time() -> Result<u64, SystemTimeError> {
-    let t = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    Ok(t)
+/// The time window a token was accepted in, as returned by [check_step](struct.TOTP.html#method.check_step)
+///
+/// A verifier can reject replays by refusing any `step` at or before the last one it accepted,
+/// and estimate the drift of the client's clock from `offset`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CheckedStep {
+    /// Position of the window relative to the current one, between `-skew` and `skew`
+    pub offset: i16,
+    /// Absolute time step of the window, as returned by [counter](struct.TOTP.html#method.counter)
+    pub step: u64,
 }
 
 /// TOTP holds informations as to how to generate an auth code and validate it. Its [secret](struct.TOTP.html#structfield.secret) field is sensitive data, treat it accordingly
+///
+/// The secret is redacted from the `Debug` output, and wiped from memory on drop.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
@@ -160,8 +268,15 @@ pub struct TOTP {
     pub step: u64,
     /// As per [rfc-4226](https://tools.ietf.org/html/rfc4226#section-4) the secret should come from a strong source, most likely a CSPRNG. It should be at least 128 bits, but 160 are recommended
     ///
-    /// non-encoded value
-    pub secret: Vec<u8>,
+    /// non-encoded value, read it with [expose_secret](struct.SecretBytes.html#method.expose_secret)
+    pub secret: SecretBytes,
+    /// Unix time at which step 0 starts, `T0` in [rfc-6238](https://tools.ietf.org/html/rfc6238#section-4.1). Almost always 0
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub t0: u64,
+    /// How tokens are rendered, see [encoding](struct.TOTP.html#method.encoding). `None` picks the [default of the algorithm](enum.Algorithm.html#method.default_encoding)
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    #[cfg_attr(feature = "zeroize", zeroize(skip))]
+    pub encoding: Option<Encoding>,
     #[cfg(feature = "otpauth")]
     /// The "Github" part of "Github:constantoine@github.com". Must not contain a colon `:`
     /// For example, the name of your service/website.
@@ -189,7 +304,13 @@ impl PartialEq for TOTP {
         if self.step != other.step {
             return false;
         }
-        constant_time_eq(self.secret.as_ref(), other.secret.as_ref())
+        if self.t0 != other.t0 {
+            return false;
+        }
+        if self.encoding() != other.encoding() {
+            return false;
+        }
+        self.secret == other.secret
     }
 }
 
@@ -219,7 +340,7 @@ impl core::fmt::Display for TOTP {
     }
 }
 
-#[cfg(all(feature = "gen_secret", not(feature = "otpauth")))]
+#[cfg(all(feature = "gen_secret", feature = "std", not(feature = "otpauth")))]
 impl Default for TOTP {
     fn default() -> Self {
         return TOTP::new(
@@ -227,13 +348,13 @@ impl Default for TOTP {
             6,
             1,
             30,
-            Secret::generate_secret().to_bytes().unwrap(),
+            Secret::default().to_bytes().unwrap(),
         )
         .unwrap();
     }
 }
 
-#[cfg(all(feature = "gen_secret", feature = "otpauth"))]
+#[cfg(all(feature = "gen_secret", feature = "std", feature = "otpauth"))]
 impl Default for TOTP {
     fn default() -> Self {
         TOTP::new(
@@ -241,7 +362,7 @@ impl Default for TOTP {
             6,
             1,
             30,
-            Secret::generate_secret().to_bytes().unwrap(),
+            Secret::default().to_bytes().unwrap(),
             None,
             "".to_string(),
         )
@@ -249,16 +370,32 @@ impl Default for TOTP {
     }
 }
 
+/// What an otpauth URL describes, before it is checked by [TOTP::new] or not
+#[cfg(feature = "otpauth")]
+struct UrlParts {
+    algorithm: Algorithm,
+    digits: usize,
+    skew: u8,
+    step: u64,
+    secret: Vec<u8>,
+    t0: u64,
+    encoding: Option<Encoding>,
+    issuer: Option<String>,
+    account_name: String,
+}
+
 impl TOTP {
     #[cfg(feature = "otpauth")]
     /// Will create a new instance of TOTP with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values
     ///
+    /// [builder](struct.TOTP.html#method.builder) names each of them instead, which is harder to get wrong.
+    ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP, Algorithm};
-    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".to_string());
+    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     /// let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret.to_bytes().unwrap(), None, "".to_string()).unwrap();
     /// ```
     /// * `digits`: MUST be between 6 & 8
@@ -301,11 +438,11 @@ impl TOTP {
     /// Will create a new instance of TOTP with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values. This is unchecked and does not check the `digits` and `secret` size
     ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP, Algorithm};
-    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".to_string());
+    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     /// let totp = TOTP::new_unchecked(Algorithm::SHA1, 6, 1, 30, secret.to_bytes().unwrap(), None, "".to_string());
     /// ```
     pub fn new_unchecked(
@@ -322,7 +459,9 @@ impl TOTP {
             digits,
             skew,
             step,
-            secret,
+            secret: secret.into(),
+            t0: 0,
+            encoding: None,
             issuer,
             account_name,
         }
@@ -331,12 +470,14 @@ impl TOTP {
     #[cfg(not(feature = "otpauth"))]
     /// Will create a new instance of TOTP with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values
     ///
+    /// [builder](struct.TOTP.html#method.builder) names each of them instead, which is harder to get wrong.
+    ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP, Algorithm};
-    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".to_string());
+    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     /// let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret.to_bytes().unwrap()).unwrap();
     /// ```
     /// * `digits`: MUST be between 6 & 8
@@ -361,11 +502,11 @@ impl TOTP {
     /// Will create a new instance of TOTP with given parameters. See [the doc](struct.TOTP.html#fields) for reference as to how to choose those values. This is unchecked and does not check the `digits` and `secret` size
     ///
     /// # Description
-    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded(String)`
+    /// * `secret`: expect a non-encoded value, to pass in base32 string use `Secret::Encoded`
     ///
     /// ```rust
     /// use totp_rs::{Secret, TOTP, Algorithm};
-    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".to_string());
+    /// let secret = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
     /// let totp = TOTP::new_unchecked(Algorithm::SHA1, 6, 1, 30, secret.to_bytes().unwrap());
     /// ```
     pub fn new_unchecked(
@@ -380,7 +521,9 @@ impl TOTP {
             digits,
             skew,
             step,
-            secret,
+            secret: secret.into(),
+            t0: 0,
+            encoding: None,
         }
     }
 
@@ -395,153 +538,218 @@ impl TOTP {
 
     /// Will sign the given timestamp
     pub fn sign(&self, time: u64) -> Vec<u8> {
-        self.algorithm.sign(
-            self.secret.as_ref(),
-            (time / self.step).to_be_bytes().as_ref(),
-        )
+        self.prepare().sign(time, |hash| hash.to_vec())
     }
 
     /// Will generate a token given the provided timestamp in seconds
     pub fn generate(&self, time: u64) -> String {
-        let result: &[u8] = &self.sign(time);
-        let offset = (result.last().unwrap() & 15) as usize;
-        #[allow(unused_mut)]
-        let mut result =
-            u32::from_be_bytes(result[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
-
-        match self.algorithm {
-            Algorithm::SHA1 | Algorithm::SHA256 | Algorithm::SHA512 => format!(
-                "{1:00$}",
-                self.digits,
-                result % 10_u32.pow(self.digits as u32)
-            ),
-            #[cfg(feature = "steam")]
-            Algorithm::Steam => (0..self.digits)
-                .map(|_| {
-                    let c = STEAM_CHARS
-                        .chars()
-                        .nth(result as usize % STEAM_CHARS.len())
-                        .unwrap();
-                    result /= STEAM_CHARS.len() as u32;
-                    c
-                })
-                .collect(),
+        self.prepare().generate(time)
+    }
+
+    /// Will generate a token given the provided timestamp in seconds, writing it at the start of `buf` rather than allocating
+    ///
+    /// Returns `None` if `buf` is shorter than the token, [digits](struct.TOTP.html#structfield.digits) long unless the [encoding](struct.TOTP.html#method.encoding) groups them.
+    pub fn generate_into<'a>(&self, time: u64, buf: &'a mut [u8]) -> Option<&'a str> {
+        self.prepare().generate_into(time, buf)
+    }
+
+    /// Will generate a token given the provided timestamp in seconds, as a number
+    ///
+    /// For decimal tokens, the token is this number padded with leading zeros to [digits](struct.TOTP.html#structfield.digits).
+    /// For Steam, it is the number the token's characters are derived from.
+    /// For custom alphabets, it is the number the token writes in that base.
+    pub fn generate_u32(&self, time: u64) -> u32 {
+        self.prepare().generate_u32(time)
+    }
+
+    /// How tokens are rendered, the [default of the algorithm](enum.Algorithm.html#method.default_encoding) unless [set](struct.TOTP.html#structfield.encoding)
+    pub fn encoding(&self) -> Cow<'_, Encoding> {
+        match &self.encoding {
+            Some(encoding) => Cow::Borrowed(encoding),
+            None => Cow::Owned(self.algorithm.default_encoding()),
         }
     }
 
     /// Returns the timestamp of the first second for the next step
     /// given the provided timestamp in seconds
+    ///
+    /// Timestamps before [t0](struct.TOTP.html#structfield.t0) belong to step 0, so this is `t0 + step` for them.
     pub fn next_step(&self, time: u64) -> u64 {
-        let step = time / self.step;
+        let step = self.counter(time);
 
-        (step + 1) * self.step
+        self.t0 + (step + 1) * self.step
+    }
+
+    /// Returns the number of the step the timestamp falls in, counted from [t0](struct.TOTP.html#structfield.t0)
+    ///
+    /// Timestamps before `t0` belong to step 0 rather than being an error, so while
+    /// a clock is behind `t0`, [check](struct.TOTP.html#method.check) accepts the codes of step 0.
+    pub fn counter(&self, time: u64) -> u64 {
+        time.saturating_sub(self.t0) / self.step
     }
 
     /// Returns the timestamp of the first second of the next step
     /// According to system time
     #[cfg(feature = "std")]
     pub fn next_step_current(&self) -> Result<u64, SystemTimeError> {
-        let t = system_time()?;
+        self.next_step_current_with(&SystemTimeSource)
+    }
+
+    /// Returns the timestamp of the first second of the next step
+    /// according to the given [TimeSource]
+    pub fn next_step_current_with<T: TimeSource>(&self, time: &T) -> Result<u64, T::Error> {
+        let t = time.now()?;
         Ok(self.next_step(t))
     }
 
     /// Give the ttl (in seconds) of the current token
     #[cfg(feature = "std")]
     pub fn ttl(&self) -> Result<u64, SystemTimeError> {
-        let t = system_time()?;
-        Ok(self.step - (t % self.step))
+        self.ttl_with(&SystemTimeSource)
+    }
+
+    /// Give the ttl (in seconds) of the current token according to the given [TimeSource]
+    pub fn ttl_with<T: TimeSource>(&self, time: &T) -> Result<u64, T::Error> {
+        let t = time.now()?;
+        Ok(self.next_step(t) - t)
     }
 
     /// Generate a token from the current system time
     #[cfg(feature = "std")]
     pub fn generate_current(&self) -> Result<String, SystemTimeError> {
-        let t = system_time()?;
+        self.generate_current_with(&SystemTimeSource)
+    }
+
+    /// Generate a token from the time given by a [TimeSource]
+    pub fn generate_current_with<T: TimeSource>(&self, time: &T) -> Result<String, T::Error> {
+        let t = time.now()?;
         Ok(self.generate(t))
     }
 
     /// Will check if token is valid given the provided timestamp in seconds, accounting [skew](struct.TOTP.html#structfield.skew)
+    ///
+    /// A timestamp before [t0](struct.TOTP.html#structfield.t0) is treated as step 0, as in [counter](struct.TOTP.html#method.counter).
     pub fn check(&self, token: &str, time: u64) -> bool {
-        let basestep = time / self.step - (self.skew as u64);
-        for i in 0..(self.skew as u16) * 2 + 1 {
-            let step_time = (basestep + (i as u64)) * self.step;
+        self.check_step(token, time).is_some()
+    }
 
-            if constant_time_eq(self.generate(step_time).as_bytes(), token.as_bytes()) {
-                return true;
-            }
-        }
-        false
+    /// Will check if token is valid given the provided timestamp in seconds, accounting [skew](struct.TOTP.html#structfield.skew),
+    /// and tell which time step it belongs to
+    ///
+    /// Every window is evaluated even after a match, so the time taken does not depend on which one matched.
+    /// Windows before the epoch are skipped rather than wrapping around.
+    pub fn check_step(&self, token: &str, time: u64) -> Option<CheckedStep> {
+        self.prepare().check_step(token, time)
     }
 
     /// Will check if token is valid by current system time, accounting [skew](struct.TOTP.html#structfield.skew)
     #[cfg(feature = "std")]
     pub fn check_current(&self, token: &str) -> Result<bool, SystemTimeError> {
-        let t = system_time()?;
+        self.check_current_with(token, &SystemTimeSource)
+    }
+
+    /// Will check if token is valid at the time given by a [TimeSource], accounting [skew](struct.TOTP.html#structfield.skew)
+    pub fn check_current_with<T: TimeSource>(
+        &self,
+        token: &str,
+        time: &T,
+    ) -> Result<bool, T::Error> {
+        let t = time.now()?;
         Ok(self.check(token, t))
     }
 
+    /// Same as [check_step](struct.TOTP.html#method.check_step), by current system time
+    #[cfg(feature = "std")]
+    pub fn check_step_current(&self, token: &str) -> Result<Option<CheckedStep>, SystemTimeError> {
+        self.check_step_current_with(token, &SystemTimeSource)
+    }
+
+    /// Same as [check_step](struct.TOTP.html#method.check_step), at the time given by a [TimeSource]
+    pub fn check_step_current_with<T: TimeSource>(
+        &self,
+        token: &str,
+        time: &T,
+    ) -> Result<Option<CheckedStep>, T::Error> {
+        let t = time.now()?;
+        Ok(self.check_step(token, t))
+    }
+
     /// Will return the base32 representation of the secret, which might be useful when users want to manually add the secret to their authenticator
     pub fn get_secret_base32(&self) -> String {
         base32::encode(
             base32::Alphabet::RFC4648 { padding: false },
-            self.secret.as_ref(),
+            self.secret.expose_secret(),
         )
     }
 
     /// Generate a TOTP from the standard otpauth URL
     #[cfg(feature = "otpauth")]
     pub fn from_url<S: AsRef<str>>(url: S) -> Result<TOTP, TotpUrlError> {
-        let (algorithm, digits, skew, step, secret, issuer, account_name) =
-            Self::parts_from_url(url)?;
-        TOTP::new(algorithm, digits, skew, step, secret, issuer, account_name)
+        let parts = Self::parts_from_url(url)?;
+        let mut totp = TOTP::new(
+            parts.algorithm,
+            parts.digits,
+            parts.skew,
+            parts.step,
+            parts.secret,
+            parts.issuer,
+            parts.account_name,
+        )?;
+        totp.t0 = parts.t0;
+        totp.encoding = parts.encoding;
+        Ok(totp)
     }
 
     /// Generate a TOTP from the standard otpauth URL, using `TOTP::new_unchecked` internally
     #[cfg(feature = "otpauth")]
     pub fn from_url_unchecked<S: AsRef<str>>(url: S) -> Result<TOTP, TotpUrlError> {
-        let (algorithm, digits, skew, step, secret, issuer, account_name) =
-            Self::parts_from_url(url)?;
-        Ok(TOTP::new_unchecked(
-            algorithm,
-            digits,
-            skew,
-            step,
-            secret,
-            issuer,
-            account_name,
-        ))
+        let parts = Self::parts_from_url(url)?;
+        let mut totp = TOTP::new_unchecked(
+            parts.algorithm,
+            parts.digits,
+            parts.skew,
+            parts.step,
+            parts.secret,
+            parts.issuer,
+            parts.account_name,
+        );
+        totp.t0 = parts.t0;
+        totp.encoding = parts.encoding;
+        Ok(totp)
     }
 
     /// Parse the TOTP parts from the standard otpauth URL
     #[cfg(feature = "otpauth")]
-    fn parts_from_url<S: AsRef<str>>(
-        url: S,
-    ) -> Result<(Algorithm, usize, u8, u64, Vec<u8>, Option<String>, String), TotpUrlError> {
+    fn parts_from_url<S: AsRef<str>>(url: S) -> Result<UrlParts, TotpUrlError> {
         let mut algorithm = Algorithm::SHA1;
         let mut digits = 6;
         let mut step = 30;
+        let mut t0 = 0;
+        let mut symbols = None;
+        let mut group = None;
+        let mut separator = ' ';
         let mut secret = Vec::new();
         let mut issuer: Option<String> = None;
         let mut account_name: String;
 
-        let url = Url::parse(url.as_ref()).map_err(TotpUrlError::Url)?;
-        if url.scheme() != "otpauth" {
-            return Err(TotpUrlError::Scheme(url.scheme().to_string()));
+        let url = otpauth::split(url.as_ref())?;
+        if url.scheme != "otpauth" {
+            return Err(TotpUrlError::Scheme(url.scheme));
         }
-        match url.host() {
-            Some(Host::Domain("totp")) => {}
+        match url.host {
+            "totp" => {}
             #[cfg(feature = "steam")]
-            Some(Host::Domain("steam")) => {
+            "steam" => {
                 algorithm = Algorithm::Steam;
             }
-            _ => {
-                return Err(TotpUrlError::Host(url.host().unwrap().to_string()));
+            host => {
+                return Err(TotpUrlError::Host(host.to_string()));
             }
         }
 
-        let path = url.path().trim_start_matches('/');
-        let path = urlencoding::decode(path)
-            .map_err(|_| TotpUrlError::AccountNameDecoding(path.to_string()))?
-            .to_string();
+        let path = url.path.trim_start_matches('/');
+        let path = otpauth::decode(path)
+            .ok_or_else(|| TotpUrlError::AccountNameDecoding(path.to_string()))?;
         if path.contains(':') {
             let parts = path.split_once(':').unwrap();
             issuer = Some(parts.0.to_owned());
@@ -550,21 +758,24 @@ impl TOTP {
             account_name = path;
         }
 
-        account_name = urlencoding::decode(account_name.as_str())
-            .map_err(|_| TotpUrlError::AccountName(account_name.to_string()))?
-            .to_string();
+        account_name = otpauth::decode(account_name.as_str())
+            .ok_or_else(|| TotpUrlError::AccountName(account_name.to_string()))?;
 
-        for (key, value) in url.query_pairs() {
-            match key.as_ref() {
+        for (key, value) in otpauth::query_pairs(url.query) {
+            match key.as_str() {
                 #[cfg(feature = "steam")]
                 "algorithm" if algorithm == Algorithm::Steam => {
                     // Do not change used algorithm if this is Steam
                 }
                 "algorithm" => {
-                    algorithm = match value.as_ref() {
+                    algorithm = match value.as_str() {
                         "SHA1" => Algorithm::SHA1,
+                        "SHA224" => Algorithm::SHA224,
                         "SHA256" => Algorithm::SHA256,
+                        "SHA384" => Algorithm::SHA384,
                         "SHA512" => Algorithm::SHA512,
+                        "SHA512_224" => Algorithm::SHA512_224,
+                        "SHA512_256" => Algorithm::SHA512_256,
                         _ => return Err(TotpUrlError::Algorithm(value.to_string())),
                     }
                 }
@@ -578,10 +789,37 @@ impl TOTP {
                         .parse::<u64>()
                         .map_err(|_| TotpUrlError::Step(value.to_string()))?;
                 }
+                // Not part of the Key URI format, T0 being almost always 0
+                "x-t0" => {
+                    t0 = value
+                        .parse::<u64>()
+                        .map_err(|_| TotpUrlError::T0(value.to_string()))?;
+                }
+                // Not part of the Key URI format either, see get_url
+                "x-symbols" => {
+                    symbols = Some(
+                        Symbols::new(&value)
+                            .ok_or_else(|| TotpUrlError::Encoding(value.clone()))?,
+                    );
+                }
+                "x-group" => {
+                    group = Some(
+                        value
+                            .parse::<usize>()
+                            .map_err(|_| TotpUrlError::Encoding(value.to_string()))?,
+                    );
+                }
+                "x-separator" => {
+                    let mut chars = value.chars();
+                    separator = match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(TotpUrlError::Encoding(value.to_string())),
+                    };
+                }
                 "secret" => {
                     secret = base32::decode(
                         base32::Alphabet::RFC4648 { padding: false },
-                        value.as_ref(),
+                        value.as_str(),
                     )
                     .ok_or_else(|| TotpUrlError::Secret(value.to_string()))?;
                 }
@@ -589,10 +827,10 @@ impl TOTP {
                 "issuer" if value.to_lowercase() == "steam" => {
                     algorithm = Algorithm::Steam;
                     digits = 5;
-                    issuer = Some(value.into());
+                    issuer = Some(value);
                 }
                 "issuer" => {
-                    let param_issuer: String = value.into();
+                    let param_issuer = value;
                     if issuer.is_some() && param_issuer.as_str() != issuer.as_ref().unwrap() {
                         return Err(TotpUrlError::IssuerMistmatch(
                             issuer.as_ref().unwrap().to_string(),
@@ -620,23 +858,57 @@ impl TOTP {
             return Err(TotpUrlError::Secret("".to_string()));
         }
 
-        Ok((algorithm, digits, 1, step, secret, issuer, account_name))
+        let mut encoding = symbols.map(Encoding::Custom);
+        if let Some(size) = group {
+            let ungrouped = encoding.unwrap_or_else(|| algorithm.default_encoding());
+            encoding = Some(ungrouped.grouped(size, separator));
+        }
+
+        Ok(UrlParts {
+            algorithm,
+            digits,
+            skew: 1,
+            step,
+            secret,
+            t0,
+            encoding,
+            issuer,
+            account_name,
+        })
     }
 
     /// Will generate a standard URL used to automatically add TOTP auths. Usually used with qr codes
     ///
     /// Label and issuer will be URL-encoded if needed be
     /// Secret will be base 32'd without padding, as per RFC.
+    /// [Algorithm::SHA512_224] and [Algorithm::SHA512_256] are written with names
+    /// that are not part of the Key URI format.
+    ///
+    /// Like [t0](struct.TOTP.html#structfield.t0), an [Encoding::Custom] or [Encoding::Grouped] is kept
+    /// in `x-symbols`, `x-group` and `x-separator` parameters that other apps ignore.
+    /// Of groupings nested in each other, only the outermost is kept.
     #[cfg(feature = "otpauth")]
     pub fn get_url(&self) -> String {
+        let encoding = self.encoding();
+        let mut ungrouped = &*encoding;
+        let mut grouping = None;
+        while let Encoding::Grouped {
+            encoding,
+            size,
+            separator,
+        } = ungrouped
+        {
+            grouping = grouping.or(Some((size, separator)));
+            ungrouped = encoding;
+        }
         #[allow(unused_mut)]
         let mut host = "totp";
         #[cfg(feature = "steam")]
-        if self.algorithm == Algorithm::Steam {
+        if *ungrouped == Encoding::Steam {
             host = "steam";
         }
-        let account_name = urlencoding::encode(self.account_name.as_str()).to_string();
-        let mut params = vec![format!("secret={}", self.get_secret_base32())];
+        let account_name = otpauth::encode(self.account_name.as_str());
+        let mut params = alloc::vec![format!("secret={}", self.get_secret_base32())];
         if self.digits != 6 {
             params.push(format!("digits={}", self.digits));
         }
@@ -644,7 +916,7 @@ impl TOTP {
             params.push(format!("algorithm={}", self.algorithm));
         }
         let label = if let Some(issuer) = &self.issuer {
-            let issuer = urlencoding::encode(issuer);
+            let issuer = otpauth::encode(issuer);
             params.push(format!("issuer={}", issuer));
             format!("{}:{}", issuer, account_name)
         } else {
@@ -653,6 +925,16 @@ impl TOTP {
         if self.step != 30 {
             params.push(format!("period={}", self.step));
         }
+        if self.t0 != 0 {
+            params.push(format!("x-t0={}", self.t0));
+        }
+        if let Encoding::Custom(symbols) = ungrouped {
+            params.push(format!("x-symbols={}", otpauth::encode(symbols.as_str())));
+        }
+        if let Some((size, separator)) = grouping {
+            let separator = otpauth::encode(separator.encode_utf8(&mut [0; 4]));
+            params.push(format!("x-group={}&x-separator={}", size, separator));
+        }
 
         format!("otpauth://{}/{}?{}", host, label, params.join("&"))
     }
@@ -704,9 +986,12 @@ impl TOTP {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::prelude::rust_2021::*;
+    #[cfg(all(feature = "std", not(feature = "otpauth")))]
+    use std::time::SystemTime;
 
     #[test]
-    #[cfg(feature = "gen_secret")]
+    #[cfg(all(feature = "gen_secret", feature = "std"))]
     fn default_values() {
         let totp = TOTP::default();
         assert_eq!(totp.algorithm, Algorithm::SHA1);
@@ -906,7 +1191,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(all(feature = "otpauth", feature = "gen_secret"))]
+    #[cfg(all(feature = "otpauth", feature = "gen_secret", feature = "std"))]
     fn ttl() {
         let secret = Secret::default();
         let totp_rfc = Rfc6238::with_defaults(secret.to_bytes().unwrap()).unwrap();
@@ -915,7 +1200,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "otpauth")]
+    #[cfg(all(feature = "std", feature = "otpauth"))]
     fn ttl_ok() {
         let totp = TOTP::new(
             Algorithm::SHA512,
@@ -948,7 +1233,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(not(feature = "otpauth"))]
+    #[cfg(all(feature = "std", not(feature = "otpauth")))]
     fn generate_token_current() {
         let totp = TOTP::new(Algorithm::SHA1, 6, 1, 1, "TestSecretSuperSecret".into()).unwrap();
         let time = SystemTime::now()
@@ -975,6 +1260,152 @@ mod tests {
         assert_eq!(totp.generate(1000).as_str(), "473536");
     }
 
+    /// Timestamps of [RFC 6238 appendix B](https://tools.ietf.org/html/rfc6238#appendix-B), and two more
+    const RFC_TIMES: [u64; 6] = [
+        59,
+        1111111109,
+        1111111111,
+        1234567890,
+        2000000000,
+        20000000000,
+    ];
+
+    /// RFC 6238 appendix B for SHA1, SHA256 and SHA512. The other SHA-2 variants are not in the
+    /// RFC; their seeds follow the same pattern, one byte per bit of output, and their codes
+    /// come from another HMAC implementation.
+    const RFC_VECTORS: [(Algorithm, usize, [&str; 6]); 7] = [
+        (
+            Algorithm::SHA1,
+            20,
+            [
+                "94287082", "07081804", "14050471", "89005924", "69279037", "65353130",
+            ],
+        ),
+        (
+            Algorithm::SHA224,
+            28,
+            [
+                "32201820", "82019503", "98501000", "25631841", "97691324", "81596645",
+            ],
+        ),
+        (
+            Algorithm::SHA256,
+            32,
+            [
+                "46119246", "68084774", "67062674", "91819424", "90698825", "77737706",
+            ],
+        ),
+        (
+            Algorithm::SHA384,
+            48,
+            [
+                "12260385", "93607533", "30635264", "29066410", "45679189", "33578931",
+            ],
+        ),
+        (
+            Algorithm::SHA512,
+            64,
+            [
+                "90693936", "25091201", "99943326", "93441116", "38618901", "47863826",
+            ],
+        ),
+        (
+            Algorithm::SHA512_224,
+            28,
+            [
+                "94469289", "73230696", "45148669", "15216427", "24095659", "83924651",
+            ],
+        ),
+        (
+            Algorithm::SHA512_256,
+            32,
+            [
+                "00441233", "97406494", "46180615", "69345394", "14506846", "83211259",
+            ],
+        ),
+    ];
+
+    #[test]
+    fn rfc_test_vectors() {
+        let seed = b"1234567890".repeat(7);
+        for (algorithm, length, codes) in RFC_VECTORS {
+            #[cfg(not(feature = "otpauth"))]
+            let totp = TOTP::new_unchecked(algorithm, 8, 0, 30, seed[..length].to_vec());
+            #[cfg(feature = "otpauth")]
+            let totp = TOTP::new_unchecked(
+                algorithm,
+                8,
+                0,
+                30,
+                seed[..length].to_vec(),
+                None,
+                "".to_string(),
+            );
+            for (time, code) in RFC_TIMES.iter().zip(codes) {
+                assert_eq!(totp.generate(*time), code, "{} at {}", algorithm, time);
+                assert!(totp.check(code, *time));
+            }
+        }
+    }
+
+    #[test]
+    fn algorithm_display() {
+        let names: Vec<String> = RFC_VECTORS
+            .iter()
+            .map(|(algorithm, _, _)| algorithm.to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "SHA1",
+                "SHA224",
+                "SHA256",
+                "SHA384",
+                "SHA512",
+                "SHA512_224",
+                "SHA512_256"
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn generates_token_into() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 1, "TestSecretSuperSecret".into()).unwrap();
+        let mut buf = [b'x'; 8];
+        assert_eq!(totp.generate_into(1000, &mut buf), Some("659761"));
+        assert_eq!(&buf, b"659761xx");
+        assert_eq!(totp.generate_into(1000, &mut [0; 5]), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn generates_token_u32() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 1, "TestSecretSuperSecret".into()).unwrap();
+        assert_eq!(totp.generate_u32(1000), 659761);
+        let totp = TOTP::new(
+            Algorithm::SHA256,
+            8,
+            1,
+            30,
+            (*b"12345678901234567890123456789012").into(),
+        )
+        .unwrap();
+        assert_eq!(totp.generate_u32(59), 46119246);
+        assert_eq!(totp.generate_u32(1111111109), 68084774);
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn generates_token_leading_zeros() {
+        let totp =
+            TOTP::new_unchecked(Algorithm::SHA1, 12, 1, 30, b"12345678901234567890".to_vec());
+        assert_eq!(totp.generate(59), "001094287082");
+        assert_eq!(totp.generate_u32(59), 1094287082);
+        assert!(totp.check("001094287082", 59));
+        assert!(!totp.check("1094287082", 59));
+    }
+
     #[test]
     #[cfg(not(feature = "otpauth"))]
     fn checks_token() {
@@ -990,7 +1421,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(not(feature = "otpauth"))]
+    #[cfg(all(feature = "std", not(feature = "otpauth")))]
     fn checks_token_current() {
         let totp = TOTP::new(Algorithm::SHA1, 6, 0, 1, "TestSecretSuperSecret".into()).unwrap();
         assert!(totp
@@ -1008,6 +1439,71 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn checks_token_step() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 1, "TestSecretSuperSecret".into()).unwrap();
+        assert_eq!(
+            totp.check_step("174269", 1000),
+            Some(CheckedStep {
+                offset: -1,
+                step: 999
+            })
+        );
+        assert_eq!(
+            totp.check_step("659761", 1000),
+            Some(CheckedStep {
+                offset: 0,
+                step: 1000
+            })
+        );
+        assert_eq!(
+            totp.check_step("260393", 1000),
+            Some(CheckedStep {
+                offset: 1,
+                step: 1001
+            })
+        );
+        assert_eq!(totp.check_step("bogus", 1000), None);
+        assert_eq!(
+            totp.check_step_current_with("659761", &FixedTime(1000)),
+            Ok(Some(CheckedStep {
+                offset: 0,
+                step: 1000
+            }))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn checks_token_near_epoch() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 255, 30, "TestSecretSuperSecret".into()).unwrap();
+        let token = totp.generate(0);
+        assert_eq!(
+            totp.check_step(&token, 59),
+            Some(CheckedStep {
+                offset: -1,
+                step: 0
+            })
+        );
+        assert!(totp.check(&totp.generate(30 * 255), 0));
+        assert!(!totp.check(&totp.generate(30 * 256), 0));
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn checks_token_near_end_of_time() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 1, "TestSecretSuperSecret".into()).unwrap();
+        let token = totp.generate(u64::MAX);
+        assert_eq!(
+            totp.check_step(&token, u64::MAX),
+            Some(CheckedStep {
+                offset: 0,
+                step: u64::MAX
+            })
+        );
+    }
+
     #[test]
     #[cfg(not(feature = "otpauth"))]
     fn next_step() {
@@ -1019,12 +1515,67 @@ mod tests {
 
     #[test]
     #[cfg(not(feature = "otpauth"))]
+    fn debug_redacts_secret() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, "TestSecretSuperSecret".into()).unwrap();
+        let debug = format!("{:?}", totp);
+        assert!(debug.contains("secret: SecretBytes([REDACTED])"));
+        assert!(!debug.contains("84, 101, 115, 116"));
+        assert_eq!(totp.secret.expose_secret(), b"TestSecretSuperSecret");
+    }
+
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn honours_t0() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, "TestSecretSuperSecret".into()).unwrap();
+        let mut shifted = totp.clone();
+        shifted.t0 = 1000;
+        assert_ne!(totp, shifted);
+        assert_eq!(shifted.counter(1000), 0);
+        assert_eq!(shifted.counter(999), 0);
+        assert_eq!(shifted.counter(1059), 1);
+        assert_eq!(shifted.generate(1045), totp.generate(45));
+        assert!(shifted.check(&totp.generate(15), 1045));
+        assert!(!shifted.check(&totp.generate(1045), 1045));
+        assert_eq!(
+            shifted.check_step(&totp.generate(75), 1045),
+            Some(CheckedStep { offset: 1, step: 2 })
+        );
+        assert_eq!(shifted.next_step(0), 1030);
+        assert_eq!(shifted.next_step(1029), 1030);
+        assert_eq!(shifted.next_step(1030), 1060);
+        assert_eq!(shifted.ttl_with(&FixedTime(1045)), Ok(15));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(feature = "otpauth")))]
     fn next_step_current() {
         let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, "TestSecretSuperSecret".into()).unwrap();
-        let t = system_time().unwrap();
+        let t = SystemTimeSource.now().unwrap();
         assert!(totp.next_step_current().unwrap() == totp.next_step(t));
     }
 
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn current_with_time_source() {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, "TestSecretSuperSecret".into()).unwrap();
+        let clock = FixedTime(1000);
+        assert_eq!(totp.generate_current_with(&clock), Ok(totp.generate(1000)));
+        assert_eq!(
+            totp.check_current_with(&totp.generate(970), &clock),
+            Ok(true)
+        );
+        assert_eq!(
+            totp.check_current_with(&totp.generate(940), &clock),
+            Ok(false)
+        );
+        assert_eq!(totp.ttl_with(&clock), Ok(20));
+        assert_eq!(totp.next_step_current_with(&clock), Ok(1020));
+
+        let broken = || Err::<u64, &str>("no clock");
+        assert_eq!(totp.generate_current_with(&broken), Err("no clock"));
+        assert_eq!(totp.ttl_with(&broken), Err("no clock"));
+    }
+
     #[test]
     #[cfg(feature = "otpauth")]
     fn from_url_err() {
@@ -1044,7 +1595,7 @@ mod tests {
             TOTP::from_url("otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ")
                 .unwrap();
         assert_eq!(
-            totp.secret,
+            totp.secret.expose_secret(),
             base32::decode(
                 base32::Alphabet::RFC4648 { padding: false },
                 "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"
@@ -1062,7 +1613,7 @@ mod tests {
     fn from_url_query() {
         let totp = TOTP::from_url("otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&digits=8&period=60&algorithm=SHA256").unwrap();
         assert_eq!(
-            totp.secret,
+            totp.secret.expose_secret(),
             base32::decode(
                 base32::Alphabet::RFC4648 { padding: false },
                 "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"
@@ -1080,7 +1631,7 @@ mod tests {
     fn from_url_query_sha512() {
         let totp = TOTP::from_url("otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&digits=8&period=60&algorithm=SHA512").unwrap();
         assert_eq!(
-            totp.secret,
+            totp.secret.expose_secret(),
             base32::decode(
                 base32::Alphabet::RFC4648 { padding: false },
                 "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"
@@ -1093,6 +1644,25 @@ mod tests {
         assert_eq!(totp.step, 60);
     }
 
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn from_url_other_sha2() {
+        for algorithm in [
+            Algorithm::SHA224,
+            Algorithm::SHA384,
+            Algorithm::SHA512_224,
+            Algorithm::SHA512_256,
+        ] {
+            let url = format!(
+                "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&algorithm={}",
+                algorithm
+            );
+            let totp = TOTP::from_url(&url).unwrap();
+            assert_eq!(totp.algorithm, algorithm);
+            assert_eq!(TOTP::from_url(totp.get_url()).unwrap(), totp);
+        }
+    }
+
     #[test]
     #[cfg(feature = "otpauth")]
     fn from_url_to_url() {
@@ -1115,7 +1685,7 @@ mod tests {
     fn from_url_unknown_param() {
         let totp = TOTP::from_url("otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&digits=8&period=60&algorithm=SHA256&foo=bar").unwrap();
         assert_eq!(
-            totp.secret,
+            totp.secret.expose_secret(),
             base32::decode(
                 base32::Alphabet::RFC4648 { padding: false },
                 "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"
@@ -1189,7 +1759,7 @@ mod tests {
     fn from_url_query_issuer() {
         let totp = TOTP::from_url("otpauth://totp/GitHub:test?issuer=GitHub&secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&digits=8&period=60&algorithm=SHA256").unwrap();
         assert_eq!(
-            totp.secret,
+            totp.secret.expose_secret(),
             base32::decode(
                 base32::Alphabet::RFC4648 { padding: false },
                 "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"
@@ -1212,6 +1782,104 @@ mod tests {
         assert!(matches!(err, TotpUrlError::Scheme(_)));
     }
 
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn from_url_malformed() {
+        assert_eq!(
+            TOTP::from_url("totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"),
+            Err(TotpUrlError::Url(UrlParseError::RelativeUrlWithoutBase))
+        );
+        assert_eq!(
+            TOTP::from_url("otpauth:///GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"),
+            Err(TotpUrlError::Url(UrlParseError::EmptyHost))
+        );
+        assert_eq!(
+            TOTP::from_url("otpauth://hotp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"),
+            Err(TotpUrlError::Host("hotp".to_string()))
+        );
+        assert_eq!(
+            TOTP::from_url("otpauth://totp/GitHub:%C3?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ"),
+            Err(TotpUrlError::AccountNameDecoding("GitHub:%C3".to_string()))
+        );
+        assert_eq!(
+            TOTP::from_url(
+                "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFO%21"
+            ),
+            Err(TotpUrlError::Secret(
+                "KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFO!".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn from_url_decodes_query() {
+        let totp = TOTP::from_url("otpauth://totp/Acme%20Corp:Jane%20Doe?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&issuer=Acme+Corp#ignored").unwrap();
+        assert_eq!(totp.issuer.as_deref(), Some("Acme Corp"));
+        assert_eq!(totp.account_name, "Jane Doe");
+        assert_eq!(
+            totp.get_url(),
+            "otpauth://totp/Acme%20Corp:Jane%20Doe?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&issuer=Acme%20Corp"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn from_url_t0() {
+        let url = "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&issuer=GitHub&x-t0=1000";
+        let totp = TOTP::from_url(url).unwrap();
+        assert_eq!(totp.t0, 1000);
+        assert_eq!(totp.get_url(), url);
+        assert_eq!(TOTP::from_url_unchecked(url).unwrap().t0, 1000);
+
+        let totp =
+            TOTP::from_url("otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ")
+                .unwrap();
+        assert_eq!(totp.t0, 0);
+        assert!(!totp.get_url().contains("x-t0"));
+
+        assert_eq!(
+            TOTP::from_url(
+                "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&x-t0=-1"
+            ),
+            Err(TotpUrlError::T0("-1".to_string()))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "otpauth")]
+    fn from_url_encoding() {
+        let url = "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ";
+        let mut totp = TOTP::from_url(url).unwrap();
+        totp.encoding = Some(Encoding::Custom(Symbols::new("ABCDEFGHJK").unwrap()).grouped(3, '-'));
+        let url = totp.get_url();
+        assert!(url.ends_with("&x-symbols=ABCDEFGHJK&x-group=3&x-separator=-"));
+        let parsed = TOTP::from_url(&url).unwrap();
+        assert_eq!(parsed.encoding, totp.encoding);
+        assert_eq!(parsed.generate(1000), totp.generate(1000));
+
+        totp.encoding = Some(Encoding::Decimal.grouped(4, ' '));
+        let url = totp.get_url();
+        assert!(url.ends_with("&x-group=4&x-separator=%20"));
+        assert_eq!(
+            TOTP::from_url_unchecked(&url).unwrap().encoding,
+            totp.encoding
+        );
+
+        assert_eq!(
+            TOTP::from_url(
+                "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&x-symbols=AAB"
+            ),
+            Err(TotpUrlError::Encoding("AAB".to_string()))
+        );
+        assert_eq!(
+            TOTP::from_url(
+                "otpauth://totp/GitHub:test?secret=KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ&x-group=3&x-separator=--"
+            ),
+            Err(TotpUrlError::Encoding("--".to_string()))
+        );
+    }
+
     #[test]
     #[cfg(feature = "otpauth")]
     fn from_url_wrong_algo() {
@@ -0,0 +1,207 @@
+//! Just enough of URL parsing and percent-encoding for `otpauth://` URLs, with no
+//! dependency beyond `alloc`, so the same code runs in `std` and firmware builds.
+
+use crate::url_error::UrlParseError;
+use crate::TotpUrlError;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The pieces of `scheme://host/path?query#fragment`, still percent-encoded
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Parts<'a> {
+    pub scheme: String,
+    pub host: &'a str,
+    pub path: &'a str,
+    pub query: &'a str,
+}
+
+/// Will split the URL, lowercasing the scheme and ignoring the fragment
+pub(crate) fn split(url: &str) -> Result<Parts<'_>, TotpUrlError> {
+    let url = url.trim_matches(|c: char| c <= ' ');
+    let (scheme, rest) = url
+        .split_once(':')
+        .filter(|(scheme, _)| is_scheme(scheme))
+        .ok_or(TotpUrlError::Url(UrlParseError::RelativeUrlWithoutBase))?;
+    let rest = rest.split('#').next().unwrap();
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let rest = rest
+        .strip_prefix("//")
+        .ok_or_else(|| TotpUrlError::Host(String::new()))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    if host.is_empty() {
+        return Err(TotpUrlError::Url(UrlParseError::EmptyHost));
+    }
+
+    Ok(Parts {
+        scheme: scheme.to_ascii_lowercase(),
+        host,
+        path,
+        query,
+    })
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+}
+
+/// Will iterate over the `key=value` pairs of a query, decoded as in HTML forms
+///
+/// Like the `url` crate, `+` stands for a space and invalid UTF-8 is replaced rather than rejected.
+pub(crate) fn query_pairs(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_form(key), decode_form(value))
+        })
+}
+
+fn decode_form(s: &str) -> String {
+    let bytes = percent_decode(&s.replace('+', " "));
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Will percent-decode the string, leaving malformed escapes as they are
+///
+/// Returns `None` if the decoded bytes are not valid UTF-8.
+pub(crate) fn decode(s: &str) -> Option<String> {
+    String::from_utf8(percent_decode(s)).ok()
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i..] {
+            [b'%', high, low, ..] => hex_value(high).zip(hex_value(low)),
+            _ => None,
+        };
+        match escaped {
+            Some((high, low)) => {
+                decoded.push(high << 4 | low);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// Will percent-encode everything but ASCII letters, digits and `-._~`
+pub(crate) fn encode(s: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut encoded = String::with_capacity(s.len());
+    for &byte in s.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[(byte >> 4) as usize] as char);
+            encoded.push(HEX[(byte & 15) as usize] as char);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn splits_url() {
+        assert_eq!(
+            split(" OTPAUTH://totp/Git%20Hub:me?secret=ABC&digits=8#frag\n"),
+            Ok(Parts {
+                scheme: "otpauth".to_string(),
+                host: "totp",
+                path: "/Git%20Hub:me",
+                query: "secret=ABC&digits=8",
+            })
+        );
+        assert_eq!(
+            split("otpauth://totp"),
+            Ok(Parts {
+                scheme: "otpauth".to_string(),
+                host: "totp",
+                path: "",
+                query: "",
+            })
+        );
+    }
+
+    #[test]
+    fn split_errors() {
+        assert_eq!(
+            split("totp/GitHub:test"),
+            Err(TotpUrlError::Url(UrlParseError::RelativeUrlWithoutBase))
+        );
+        assert_eq!(
+            split("1otpauth://totp/"),
+            Err(TotpUrlError::Url(UrlParseError::RelativeUrlWithoutBase))
+        );
+        assert_eq!(
+            split("otpauth:///GitHub:test"),
+            Err(TotpUrlError::Url(UrlParseError::EmptyHost))
+        );
+        assert_eq!(
+            split("otpauth:totp/GitHub:test"),
+            Err(TotpUrlError::Host(String::new()))
+        );
+    }
+
+    #[test]
+    fn decodes_query() {
+        let pairs: Vec<_> = query_pairs("issuer=Git+Hub%21&&secret=ABC&flag&bad=%FF%zz").collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("issuer".to_string(), "Git Hub!".to_string()),
+                ("secret".to_string(), "ABC".to_string()),
+                ("flag".to_string(), "".to_string()),
+                ("bad".to_string(), "\u{FFFD}%zz".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(
+            decode("Github%3Aconstantoine%40github.com"),
+            Some("Github:constantoine@github.com".to_string())
+        );
+        assert_eq!(decode("100%25+%2"), Some("100%+%2".to_string()));
+        assert_eq!(decode("caf%C3%A9"), Some("café".to_string()));
+        assert_eq!(decode("%C3"), None);
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(
+            encode("constantoine@github.com"),
+            "constantoine%40github.com"
+        );
+        assert_eq!(encode("a-b_c.d~e f:g/é"), "a-b_c.d~e%20f%3Ag%2F%C3%A9");
+        assert_eq!(
+            decode(&encode("Günther: the ☃")),
+            Some("Günther: the ☃".to_string())
+        );
+    }
+}
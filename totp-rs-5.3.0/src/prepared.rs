@@ -0,0 +1,170 @@
+use crate::{CheckedStep, Encoding, Signer, TOTP};
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::ops::Range;
+
+/// A [TOTP] whose secret has already been turned into keyed HMAC states
+///
+/// Keying an HMAC hashes the secret into its inner and outer pads, which costs as much as
+/// hashing the counter itself. A `PreparedTotp` does that once, then only clones the keyed
+/// states, so keep one around when checking many tokens, scanning wide windows or generating
+/// codes in bulk.
+///
+/// ```rust
+/// use totp_rs::TOTP;
+///
+/// let totp = TOTP::builder().secret(b"TestSecretSuperSecret".to_vec()).build().unwrap();
+/// let prepared = totp.prepare();
+/// assert_eq!(prepared.generate(1000), totp.generate(1000));
+/// assert!(prepared.check(&totp.generate(1000), 1000));
+/// ```
+#[derive(Clone)]
+pub struct PreparedTotp<'a> {
+    totp: &'a TOTP,
+    signer: Signer,
+    encoding: Cow<'a, Encoding>,
+}
+
+impl TOTP {
+    /// Will key the HMAC once, for the many computations of a [PreparedTotp]
+    pub fn prepare(&self) -> PreparedTotp<'_> {
+        PreparedTotp {
+            totp: self,
+            signer: self.algorithm.signer(self.secret.expose_secret()),
+            encoding: self.encoding(),
+        }
+    }
+}
+
+impl<'a> PreparedTotp<'a> {
+    /// The [TOTP] this was prepared from
+    pub fn totp(&self) -> &'a TOTP {
+        self.totp
+    }
+
+    /// Same as [TOTP::sign], into the given closure rather than a `Vec`
+    pub(crate) fn sign<R>(&self, time: u64, f: impl FnOnce(&[u8]) -> R) -> R {
+        self.signer.sign(self.totp.counter(time), f)
+    }
+
+    /// Same as [TOTP::generate]
+    pub fn generate(&self, time: u64) -> String {
+        let mut token = alloc::vec![0; self.encoding.len(self.totp.digits)];
+        self.generate_into(time, &mut token);
+        String::from_utf8(token).unwrap()
+    }
+
+    /// Same as [TOTP::generate_into]
+    pub fn generate_into<'b>(&self, time: u64, buf: &'b mut [u8]) -> Option<&'b str> {
+        let digits = self.totp.digits;
+        let token = buf.get_mut(..self.encoding.len(digits))?;
+        let value = self.signer.truncate(self.totp.counter(time));
+        self.encoding
+            .render(value, digits, &mut |i, c| token[i] = c);
+        core::str::from_utf8(token).ok()
+    }
+
+    /// Same as [TOTP::generate_u32]
+    pub fn generate_u32(&self, time: u64) -> u32 {
+        self.code(self.totp.counter(time))
+    }
+
+    /// Will generate, as numbers, the tokens of every time step in the range
+    ///
+    /// Step `n` starts at timestamp `t0 + n * step`.
+    /// See [TOTP::generate_u32] for what the numbers mean.
+    pub fn generate_steps(&self, steps: Range<u64>) -> impl Iterator<Item = u32> + '_ {
+        steps.map(move |step| self.code(step))
+    }
+
+    fn code(&self, step: u64) -> u32 {
+        let value = self.signer.truncate(step);
+        self.encoding.reduce(value, self.totp.digits)
+    }
+
+    /// Same as [TOTP::check]
+    pub fn check(&self, token: &str, time: u64) -> bool {
+        self.check_step(token, time).is_some()
+    }
+
+    /// Same as [TOTP::check_step]
+    pub fn check_step(&self, token: &str, time: u64) -> Option<CheckedStep> {
+        let skew = self.totp.skew;
+        let mut matched = None;
+        self.check_window(token, time, skew, skew, |checked| {
+            matched = matched.or(Some(checked));
+        });
+        matched
+    }
+
+    /// Calls `matched` with every step from `past` steps before the current one to `future` steps after it that the token matches, in order
+    ///
+    /// Every window is evaluated, steps before the epoch are skipped.
+    pub(crate) fn check_window(
+        &self,
+        token: &str,
+        time: u64,
+        past: u8,
+        future: u8,
+        mut matched: impl FnMut(CheckedStep),
+    ) {
+        let current = self.totp.counter(time);
+        for offset in -(past as i16)..=future as i16 {
+            let step = if offset < 0 {
+                current.checked_sub(offset.unsigned_abs() as u64)
+            } else {
+                current.checked_add(offset as u64)
+            };
+            let step = match step {
+                Some(step) => step,
+                None => continue,
+            };
+
+            let value = self.signer.truncate(step);
+            if self.encoding.matches(value, self.totp.digits, token) {
+                matched(CheckedStep { offset, step });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Algorithm;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn checks_many_tokens() {
+        let secret = b"12345678901234567890123456789012".to_vec();
+        let totp = TOTP::builder()
+            .algorithm(Algorithm::SHA256)
+            .digits(8)
+            .secret(secret)
+            .build()
+            .unwrap();
+        let prepared = totp.prepare();
+        for (token, time) in [
+            ("46119246", 59),
+            ("68084774", 1111111109),
+            ("67062674", 1111111111),
+        ] {
+            assert_eq!(prepared.generate(time), token);
+            assert!(prepared.check(token, time + 30));
+            assert!(!prepared.check(token, time + 60));
+        }
+    }
+
+    #[test]
+    fn generate_steps() {
+        let totp = TOTP::builder()
+            .digits(8)
+            .secret(b"12345678901234567890".to_vec())
+            .build()
+            .unwrap();
+        let codes: Vec<u32> = totp.prepare().generate_steps(37037035..37037038).collect();
+        assert_eq!(codes, [totp.generate_u32(1111111079), 7081804, 14050471]);
+        assert_eq!(totp.prepare().generate_steps(5..5).count(), 0);
+    }
+}
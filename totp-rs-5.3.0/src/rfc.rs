@@ -1,7 +1,10 @@
 use crate::Algorithm;
+use crate::SecretBytes;
 use crate::TotpUrlError;
 use crate::TOTP;
 
+#[cfg(feature = "otpauth")]
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 #[cfg(feature = "serde_support")]
@@ -70,24 +73,27 @@ pub fn assert_secret_length(secret: &[u8]) -> Result<(), Rfc6238Error> {
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Rfc6238 {
     /// SHA-1
-    algorithm: Algorithm,
+    pub(crate) algorithm: Algorithm,
     /// The number of digits composing the auth code. Per [rfc-4226](https://tools.ietf.org/html/rfc4226#section-5.3), this can oscilate between 6 and 8 digits.
-    digits: usize,
+    pub(crate) digits: usize,
     /// The recommended value per [rfc-6238](https://tools.ietf.org/html/rfc6238#section-5.2) is 1.
-    skew: u8,
+    pub(crate) skew: u8,
     /// The recommended value per [rfc-6238](https://tools.ietf.org/html/rfc6238#section-5.2) is 30 seconds.
-    step: u64,
+    pub(crate) step: u64,
     /// As per [rfc-4226](https://tools.ietf.org/html/rfc4226#section-4) the secret should come from a strong source, most likely a CSPRNG. It should be at least 128 bits, but 160 are recommended.
-    secret: Vec<u8>,
+    pub(crate) secret: SecretBytes,
+    /// The default value per [rfc-6238](https://tools.ietf.org/html/rfc6238#section-4.1) is 0, the Unix epoch.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub(crate) t0: u64,
     #[cfg(feature = "otpauth")]
     /// The "Github" part of "Github:constantoine@github.com". Must not contain a colon `:`
     /// For example, the name of your service/website.
     /// Not mandatory, but strongly recommended!
-    issuer: Option<String>,
+    pub(crate) issuer: Option<String>,
     #[cfg(feature = "otpauth")]
     /// The "constantoine@github.com" part of "Github:constantoine@github.com". Must not contain a colon `:`.
     /// For example, the name of your user's account.
-    account_name: String,
+    pub(crate) account_name: String,
 }
 
 impl Rfc6238 {
@@ -113,7 +119,8 @@ impl Rfc6238 {
             digits,
             skew: 1,
             step: 30,
-            secret,
+            secret: secret.into(),
+            t0: 0,
             issuer,
             account_name,
         })
@@ -128,7 +135,8 @@ impl Rfc6238 {
             digits,
             skew: 1,
             step: 30,
-            secret,
+            secret: secret.into(),
+            t0: 0,
         })
     }
 
@@ -157,6 +165,11 @@ impl Rfc6238 {
         Ok(())
     }
 
+    /// Set the `t0`, the Unix time at which step 0 starts.
+    pub fn t0(&mut self, value: u64) {
+        self.t0 = value;
+    }
+
     #[cfg(feature = "otpauth")]
     /// Set the `issuer`.
     pub fn issuer(&mut self, value: String) {
@@ -176,7 +189,15 @@ impl TryFrom<Rfc6238> for TOTP {
 
     /// Try to create a [TOTP](struct.TOTP.html) from a [Rfc6238](struct.Rfc6238.html) config.
     fn try_from(rfc: Rfc6238) -> Result<Self, Self::Error> {
-        TOTP::new(rfc.algorithm, rfc.digits, rfc.skew, rfc.step, rfc.secret)
+        let mut totp = TOTP::new(
+            rfc.algorithm,
+            rfc.digits,
+            rfc.skew,
+            rfc.step,
+            rfc.secret.into_vec(),
+        )?;
+        totp.t0 = rfc.t0;
+        Ok(totp)
     }
 }
 
@@ -186,20 +207,24 @@ impl TryFrom<Rfc6238> for TOTP {
 
     /// Try to create a [TOTP](struct.TOTP.html) from a [Rfc6238](struct.Rfc6238.html) config.
     fn try_from(rfc: Rfc6238) -> Result<Self, Self::Error> {
-        TOTP::new(
+        let mut totp = TOTP::new(
             rfc.algorithm,
             rfc.digits,
             rfc.skew,
             rfc.step,
-            rfc.secret,
+            rfc.secret.into_vec(),
             rfc.issuer,
             rfc.account_name,
-        )
+        )?;
+        totp.t0 = rfc.t0;
+        Ok(totp)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::prelude::rust_2021::*;
+
     #[cfg(feature = "otpauth")]
     use crate::TotpUrlError;
 
@@ -263,7 +288,7 @@ mod tests {
         let totp = TOTP::try_from(rfc);
         assert!(totp.is_ok());
         let otp = totp.unwrap();
-        assert_eq!(&otp.secret, GOOD_SECRET.as_bytes());
+        assert_eq!(otp.secret.expose_secret(), GOOD_SECRET.as_bytes());
         assert_eq!(otp.algorithm, crate::Algorithm::SHA1);
         assert_eq!(otp.digits, 8);
         assert_eq!(otp.skew, 1);
@@ -274,7 +299,7 @@ mod tests {
     #[cfg(not(feature = "otpauth"))]
     fn rfc_to_totp_ok_2() {
         let rfc = Rfc6238::with_defaults(
-            Secret::Encoded("KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ".to_string())
+            Secret::Encoded("KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ".into())
                 .to_bytes()
                 .unwrap(),
         )
@@ -288,6 +313,15 @@ mod tests {
         assert_eq!(otp.step, 30)
     }
 
+    #[test]
+    #[cfg(not(feature = "otpauth"))]
+    fn rfc_to_totp_t0() {
+        let mut rfc = Rfc6238::with_defaults(GOOD_SECRET.into()).unwrap();
+        assert_eq!(TOTP::try_from(rfc.clone()).unwrap().t0, 0);
+        rfc.t0(1000);
+        assert_eq!(TOTP::try_from(rfc).unwrap().t0, 1000);
+    }
+
     #[test]
     #[cfg(feature = "otpauth")]
     fn rfc_to_totp_fail() {
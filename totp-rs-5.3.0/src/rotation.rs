@@ -0,0 +1,210 @@
+//! Rotation of the secret of an account, without rejecting the authenticators still set up with the old one.
+//!
+//! A [RotatingTotp] generates tokens with its primary key only, but also accepts the tokens of its
+//! secondary keys, each until its own deadline. Keys carry a tag, such as a version number or a
+//! database id, and checking a token tells which key it matched:
+//!
+//! ```rust
+//! use totp_rs::{RotatingTotp, TOTP};
+//!
+//! let old = TOTP::builder().secret(b"TestSecretSuperSecret".to_vec()).build().unwrap();
+//! let new = TOTP::builder().secret(b"AnotherSecretSuperSecret".to_vec()).build().unwrap();
+//! let mut account = RotatingTotp::new(1, old.clone());
+//! // Old authenticators keep working for a week
+//! account.rotate(2, new.clone(), 1000 + 7 * 24 * 3600);
+//!
+//! assert_eq!(account.generate(1000), new.generate(1000));
+//! assert_eq!(account.check(&old.generate(1000), 1000).map(|key| *key.tag), Some(1));
+//! assert_eq!(account.check(&new.generate(1000), 1000).map(|key| *key.tag), Some(2));
+//! assert!(account.check(&old.generate(700_000), 700_000).is_none());
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{CheckedStep, TOTP};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+/// An account with one key generating tokens, and more accepting them until their deadline
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RotatingTotp<K> {
+    primary: Key<K>,
+    secondaries: Vec<SecondaryKey<K>>,
+}
+
+/// A [TOTP] and the tag it is known by
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Key<K> {
+    /// Whatever tells this key apart from the others of the account
+    pub tag: K,
+    pub totp: TOTP,
+}
+
+/// A [Key] only accepted until a deadline
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SecondaryKey<K> {
+    pub key: Key<K>,
+    /// Unix time from which tokens of this key are rejected
+    pub until: u64,
+}
+
+/// The key a token was accepted by, as returned by [RotatingTotp::check]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyMatch<'a, K> {
+    /// Tag of the matching key
+    pub tag: &'a K,
+    /// Whether it is the primary key, rather than one due to be retired
+    pub primary: bool,
+    /// The time window the token was accepted in
+    pub step: CheckedStep,
+}
+
+impl<K> RotatingTotp<K> {
+    /// Will create an account with a single key, tagged `tag`
+    pub fn new(tag: K, totp: TOTP) -> RotatingTotp<K> {
+        RotatingTotp {
+            primary: Key { tag, totp },
+            secondaries: Vec::new(),
+        }
+    }
+
+    /// The key generating tokens
+    pub fn primary(&self) -> &Key<K> {
+        &self.primary
+    }
+
+    /// The keys whose tokens are accepted too, until their deadline
+    pub fn secondaries(&self) -> &[SecondaryKey<K>] {
+        &self.secondaries
+    }
+
+    /// Will make `totp` the primary key, keeping the current one as a secondary accepted until `until`
+    pub fn rotate(&mut self, tag: K, totp: TOTP, until: u64) {
+        let previous = core::mem::replace(&mut self.primary, Key { tag, totp });
+        self.add_secondary(previous.tag, previous.totp, until);
+    }
+
+    /// Will accept the tokens of `totp` too, until `until`
+    pub fn add_secondary(&mut self, tag: K, totp: TOTP, until: u64) {
+        self.secondaries.push(SecondaryKey {
+            key: Key { tag, totp },
+            until,
+        });
+    }
+
+    /// Will forget the secondary keys whose deadline is at or before the given timestamp in seconds
+    pub fn prune(&mut self, time: u64) {
+        self.secondaries.retain(|secondary| time < secondary.until);
+    }
+
+    /// Will generate a token with the primary key, given the provided timestamp in seconds
+    pub fn generate(&self, time: u64) -> String {
+        self.primary.totp.generate(time)
+    }
+
+    /// Will check the token against the primary key, and the secondary keys not past their deadline, given the provided timestamp in seconds
+    ///
+    /// Every key is checked even after a match, so the time taken does not depend on which one matched.
+    /// If several match, the primary key wins, then the first secondary one.
+    pub fn check(&self, token: &str, time: u64) -> Option<KeyMatch<'_, K>> {
+        let mut matched = self
+            .primary
+            .totp
+            .check_step(token, time)
+            .map(|step| KeyMatch {
+                tag: &self.primary.tag,
+                primary: true,
+                step,
+            });
+        for secondary in &self.secondaries {
+            if time >= secondary.until {
+                continue;
+            }
+            let step = secondary.key.totp.check_step(token, time);
+            if let (None, Some(step)) = (&matched, step) {
+                matched = Some(KeyMatch {
+                    tag: &secondary.key.tag,
+                    primary: false,
+                    step,
+                });
+            }
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    fn totp(secret: &str) -> TOTP {
+        TOTP::builder()
+            .secret(secret.as_bytes().to_vec())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn single_key() {
+        let account = RotatingTotp::new("v1", totp("TestSecretSuperSecret"));
+        let token = account.generate(1000);
+        assert_eq!(token, totp("TestSecretSuperSecret").generate(1000));
+        let matched = account.check(&token, 1010).unwrap();
+        assert_eq!(matched.tag, &"v1");
+        assert!(matched.primary);
+        assert_eq!(matched.step.step, 33);
+        assert!(account.check(&token, 2000).is_none());
+        assert!(account.secondaries().is_empty());
+    }
+
+    #[test]
+    fn rotation() {
+        let mut account = RotatingTotp::new("v1", totp("TestSecretSuperSecret"));
+        account.rotate("v2", totp("AnotherSecretSuperSecret"), 2000);
+        account.add_secondary("backup", totp("BackupSecretSuperSecret"), 3000);
+        assert_eq!(account.primary().tag, "v2");
+        assert_eq!(account.secondaries().len(), 2);
+        assert_eq!(
+            account.generate(1000),
+            totp("AnotherSecretSuperSecret").generate(1000)
+        );
+
+        let old = totp("TestSecretSuperSecret");
+        let matched = account.check(&old.generate(1000), 1000).unwrap();
+        assert_eq!((matched.tag, matched.primary), (&"v1", false));
+        let matched = account
+            .check(&totp("BackupSecretSuperSecret").generate(2500), 2500)
+            .unwrap();
+        assert_eq!(matched.tag, &"backup");
+
+        // The deadline is exclusive
+        assert!(account.check(&old.generate(1999), 1999).is_some());
+        assert!(account.check(&old.generate(2000), 2000).is_none());
+        assert!(
+            account
+                .check(&account.generate(2000), 2000)
+                .unwrap()
+                .primary
+        );
+
+        account.prune(2000);
+        assert_eq!(account.secondaries().len(), 1);
+        assert_eq!(account.secondaries()[0].key.tag, "backup");
+        account.prune(3000);
+        assert!(account.secondaries().is_empty());
+    }
+
+    #[test]
+    fn primary_wins() {
+        let mut account = RotatingTotp::new(1, totp("TestSecretSuperSecret"));
+        account.add_secondary(2, totp("TestSecretSuperSecret"), 5000);
+        let matched = account.check(&account.generate(1000), 1000).unwrap();
+        assert_eq!((*matched.tag, matched.primary), (1, true));
+    }
+}
@@ -4,14 +4,14 @@
 //!
 //! - Create a TOTP from a "raw" secret
 //! ```
-//! # #[cfg(not(feature = "otpauth"))] {
+//! # #[cfg(all(feature = "std", not(feature = "otpauth")))] {
 //! use totp_rs::{Secret, TOTP, Algorithm};
 //!
 //! let secret = [
 //!     0x70, 0x6c, 0x61, 0x69, 0x6e, 0x2d, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0x2d, 0x73, 0x65,
 //!     0x63, 0x72, 0x65, 0x74, 0x2d, 0x31, 0x32, 0x33,
 //! ];
-//! let secret_raw = Secret::Raw(secret.to_vec());
+//! let secret_raw = Secret::Raw(secret.to_vec().into());
 //! let totp_raw = TOTP::new(
 //!     Algorithm::SHA1,
 //!     6,
@@ -26,10 +26,10 @@
 //!
 //! - Create a TOTP from a base32 encoded secret
 //! ```
-//! # #[cfg(not(feature = "otpauth"))] {
+//! # #[cfg(all(feature = "std", not(feature = "otpauth")))] {
 //! use totp_rs::{Secret, TOTP, Algorithm};
 //!
-//! let secret_b32 = Secret::Encoded(String::from("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG"));
+//! let secret_b32 = Secret::Encoded("OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG".into());
 //! let totp_b32 = TOTP::new(
 //!     Algorithm::SHA1,
 //!     6,
@@ -44,7 +44,7 @@
 //! ```
 //! - Create a TOTP from a Generated Secret
 //! ```
-//! # #[cfg(all(feature = "gen_secret", not(feature = "otpauth")))] {
+//! # #[cfg(all(feature = "gen_secret", feature = "std", not(feature = "otpauth")))] {
 //! use totp_rs::{Secret, TOTP, Algorithm};
 //!
 //! let secret_b32 = Secret::default();
@@ -61,10 +61,10 @@
 //! ```
 //! - Create a TOTP from a Generated Secret 2
 //! ```
-//! # #[cfg(all(feature = "gen_secret", not(feature = "otpauth")))] {
+//! # #[cfg(all(feature = "gen_secret", feature = "std", not(feature = "otpauth")))] {
 //! use totp_rs::{Secret, TOTP, Algorithm};
 //!
-//! let secret_b32 = Secret::generate_secret();
+//! let secret_b32 = Secret::generate_secret(&mut rand::thread_rng());
 //! let totp_b32 = TOTP::new(
 //!     Algorithm::SHA1,
 //!     6,
@@ -84,17 +84,50 @@ use base32::{self, Alphabet};
 
 use constant_time_eq::constant_time_eq;
 
+use crate::{Reveal, SecretBytes, SecretString};
+
+#[cfg(feature = "gen_secret")]
+use rand::{CryptoRng, RngCore};
+
 /// Different ways secret parsing failed.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SecretParseError {
     /// Invalid base32 input.
     ParseBase32,
+    /// Nothing but separators and padding.
+    Empty,
+    /// A character that is neither part of the encoding, a separator nor padding.
+    /// `position` counts characters, not bytes, from 0.
+    InvalidCharacter { position: usize, character: char },
+    /// No secret encodes to that many characters, once separators and padding are removed.
+    InvalidLength(usize),
+    /// The Crockford check symbol, at `position`, doesn't match the rest of the secret.
+    CheckSymbol { position: usize },
 }
 
 impl core::fmt::Display for SecretParseError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             SecretParseError::ParseBase32 => write!(f, "Could not decode base32 secret."),
+            SecretParseError::Empty => write!(f, "Secret is empty."),
+            SecretParseError::InvalidCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "Invalid character {:?} at position {} of secret.",
+                character, position
+            ),
+            SecretParseError::InvalidLength(length) => write!(
+                f,
+                "Could not decode secret of {} characters, its length is invalid.",
+                length
+            ),
+            SecretParseError::CheckSymbol { position } => write!(
+                f,
+                "Check symbol at position {} doesn't match the secret, it was likely mistyped.",
+                position
+            ),
         }
     }
 }
@@ -102,13 +135,15 @@ impl core::fmt::Display for SecretParseError {
 impl core::error::Error for Secret {}
 
 /// Shared secret between client and server to validate token against/generate token from.
-#[derive(Debug, Clone, Eq)]
+///
+/// Both `Debug` and `Display` are redacted, use [reveal](Secret::reveal) to print the secret.
+#[derive(Clone, Eq)]
 #[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
 pub enum Secret {
     /// Non-encoded "raw" secret.
-    Raw(Vec<u8>),
+    Raw(SecretBytes),
     /// Base32 encoded secret.
-    Encoded(String),
+    Encoded(SecretString),
 }
 
 impl PartialEq for Secret {
@@ -119,10 +154,10 @@ impl PartialEq for Secret {
     }
 }
 
-#[cfg(feature = "gen_secret")]
+#[cfg(all(feature = "gen_secret", feature = "std"))]
 impl Default for Secret {
     fn default() -> Self {
-        Secret::generate_secret()
+        Secret::generate_secret(&mut rand::thread_rng())
     }
 }
 
@@ -130,11 +165,13 @@ impl Secret {
     /// Get the inner String value as a Vec of bytes.
     pub fn to_bytes(&self) -> Result<Vec<u8>, SecretParseError> {
         match self {
-            Secret::Raw(s) => Ok(s.to_vec()),
-            Secret::Encoded(s) => match base32::decode(Alphabet::RFC4648 { padding: false }, s) {
-                Some(bytes) => Ok(bytes),
-                None => Err(SecretParseError::ParseBase32),
-            },
+            Secret::Raw(s) => Ok(s.expose_secret().to_vec()),
+            Secret::Encoded(s) => {
+                match base32::decode(Alphabet::RFC4648 { padding: false }, s.expose_secret()) {
+                    Some(bytes) => Ok(bytes),
+                    None => Err(SecretParseError::ParseBase32),
+                }
+            }
         }
     }
 
@@ -142,19 +179,21 @@ impl Secret {
     pub fn to_raw(&self) -> Result<Self, SecretParseError> {
         match self {
             Secret::Raw(_) => Ok(self.clone()),
-            Secret::Encoded(s) => match base32::decode(Alphabet::RFC4648 { padding: false }, s) {
-                Some(buf) => Ok(Secret::Raw(buf)),
-                None => Err(SecretParseError::ParseBase32),
-            },
+            Secret::Encoded(s) => {
+                match base32::decode(Alphabet::RFC4648 { padding: false }, s.expose_secret()) {
+                    Some(buf) => Ok(Secret::Raw(buf.into())),
+                    None => Err(SecretParseError::ParseBase32),
+                }
+            }
         }
     }
 
     /// Try to transforms a `Secret::Raw` into a `Secret::Encoded`.
     pub fn to_encoded(&self) -> Self {
         match self {
-            Secret::Raw(s) => {
-                Secret::Encoded(base32::encode(Alphabet::RFC4648 { padding: false }, s))
-            }
+            Secret::Raw(s) => Secret::Encoded(
+                base32::encode(Alphabet::RFC4648 { padding: false }, s.expose_secret()).into(),
+            ),
             Secret::Encoded(_) => self.clone(),
         }
     }
@@ -167,28 +206,220 @@ impl Secret {
     /// > The length of the shared secret MUST be at least 128 bits.
     /// > This document RECOMMENDs a shared secret length of 160 bits.
     ///
+    /// The random number generator is supplied by the caller, so this is available without `std`.
+    /// With feature `std`, `rand::thread_rng()` is a good default.
+    ///
     /// ⚠️ The generated secret is not guaranteed to be a valid UTF-8 sequence.
     #[cfg(feature = "gen_secret")]
-    pub fn generate_secret() -> Secret {
-        use rand::Rng;
+    pub fn generate_secret<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Secret {
+        Secret::generate_secret_sized(rng, 20)
+    }
+
+    /// ⚠️ requires feature `gen_secret`.
+    ///
+    /// Generate a CSPRNG binary value of `size` bytes, for example 32 bytes for a 256-bit secret.
+    ///
+    /// ⚠️ The generated secret is not guaranteed to be a valid UTF-8 sequence.
+    #[cfg(feature = "gen_secret")]
+    pub fn generate_secret_sized<R: RngCore + CryptoRng + ?Sized>(
+        rng: &mut R,
+        size: usize,
+    ) -> Secret {
+        let mut secret = alloc::vec![0u8; size];
+        rng.fill_bytes(&mut secret);
+        Secret::Raw(secret.into())
+    }
+
+    /// Will parse a secret the way issuers print it, in base32 or hexadecimal
+    ///
+    /// Whitespace and `-` separators are skipped, as is trailing `=` padding, and case does not matter.
+    /// The input is read as hexadecimal when prefixed with `0x`, or when it is valid hexadecimal but not valid base32.
+    /// Otherwise it is read as base32, so a secret made only of `A`-`F` and `2`-`7` must go through [parse_hex](Secret::parse_hex).
+    ///
+    /// ```rust
+    /// use totp_rs::Secret;
+    ///
+    /// let secret = Secret::parse("krsx g5ct mvrx ezlu kn2x azls knsw g4tf oq==").unwrap();
+    /// assert_eq!(secret.to_bytes().unwrap(), b"TestSecretSuperSecret");
+    /// let secret = Secret::parse("5465-7374-5365-6372-6574-5375-7065-7253-6563-7265-74").unwrap();
+    /// assert_eq!(secret.to_bytes().unwrap(), b"TestSecretSuperSecret");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return the position of the first offending character, or the length if there are too few or too many.
+    pub fn parse(input: &str) -> Result<Secret, SecretParseError> {
+        let trimmed = input.trim_start();
+        if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+            return Secret::parse_hex(input);
+        }
+        Secret::parse_base32(input).or_else(|e| match Secret::parse_hex(input) {
+            Ok(secret) => Ok(secret),
+            // Only hexadecimal digits, the hexadecimal error says more
+            Err(hex @ SecretParseError::InvalidLength(_)) => Err(hex),
+            Err(_) => Err(e),
+        })
+    }
+
+    /// Will parse a base32 secret, skipping separators and padding, see [parse](Secret::parse)
+    pub fn parse_base32(input: &str) -> Result<Secret, SecretParseError> {
+        Secret::parse_base32_with(input, Alphabet::RFC4648 { padding: false })
+    }
+
+    /// Will parse a base32 secret in the given alphabet, skipping separators and case like [parse_base32](Secret::parse_base32)
+    ///
+    /// Trailing `=` padding is only skipped for the RFC4648 alphabets, as `=` is a check symbol in [Alphabet::CrockfordCheck].
+    ///
+    /// ```rust
+    /// use totp_rs::{Base32Alphabet, Secret};
+    ///
+    /// let secret = Secret::parse_base32_with("kt1z-g7nu-citz-r3mw-kp4z-y3m1-kp1s-ghuf-qo", Base32Alphabet::ZBase32).unwrap();
+    /// assert_eq!(secret.to_bytes().unwrap(), b"TestSecretSuperSecret");
+    /// let secret = Secret::parse_base32_with("AHIN 6T2J CLHN 4PBK ADQN 0PBI ADIM 6SJ5 EG======", Base32Alphabet::RFC4648Hex { padding: false }).unwrap();
+    /// assert_eq!(secret.to_bytes().unwrap(), b"TestSecretSuperSecret");
+    /// ```
+    pub fn parse_base32_with(input: &str, alphabet: Alphabet) -> Result<Secret, SecretParseError> {
+        let padded = matches!(
+            alphabet,
+            Alphabet::RFC4648 { .. } | Alphabet::RFC4648Hex { .. }
+        );
+        let mut normalized = zeroize::Zeroizing::new(String::with_capacity(input.len()));
+        let mut positions = Vec::with_capacity(input.len());
+        let mut padding = None;
+        for (position, c) in significant(input) {
+            if padded && c == '=' {
+                padding.get_or_insert(position);
+                continue;
+            }
+            if let Some(position) = padding {
+                return Err(SecretParseError::InvalidCharacter {
+                    position,
+                    character: '=',
+                });
+            }
+            if !c.is_ascii() {
+                return Err(SecretParseError::InvalidCharacter {
+                    position,
+                    character: c,
+                });
+            }
+            normalized.push(c);
+            positions.push(position);
+        }
 
-        let mut rng = rand::thread_rng();
-        let mut secret: [u8; 20] = Default::default();
-        rng.fill(&mut secret[..]);
-        Secret::Raw(secret.to_vec())
+        // The check symbol doesn't count towards the length of the data
+        let data = match alphabet {
+            Alphabet::CrockfordCheck => normalized.len().saturating_sub(1),
+            _ => normalized.len(),
+        };
+        match data % 8 {
+            _ if normalized.is_empty() => return Err(SecretParseError::Empty),
+            1 | 3 | 6 => return Err(SecretParseError::InvalidLength(normalized.len())),
+            _ => {}
+        }
+
+        let mut bytes = zeroize::Zeroizing::new(alloc::vec![0u8; data * 5 / 8]);
+        if let Some(len) = base32::decode_into(alphabet, &normalized, &mut bytes) {
+            bytes.truncate(len);
+            return Ok(Secret::Raw(core::mem::take(&mut *bytes).into()));
+        }
+        // Only now that it failed, find out where
+        match base32::try_decode(alphabet, &normalized, base32::Mode::Lenient) {
+            Err(base32::DecodeError::InvalidCharacter { position, byte }) => {
+                Err(SecretParseError::InvalidCharacter {
+                    position: positions[position],
+                    character: byte as char,
+                })
+            }
+            Err(base32::DecodeError::InvalidPadding { position }) => {
+                Err(SecretParseError::InvalidCharacter {
+                    position: positions[position],
+                    character: '=',
+                })
+            }
+            Err(base32::DecodeError::CheckSymbol { position }) => {
+                Err(SecretParseError::CheckSymbol {
+                    position: positions[position],
+                })
+            }
+            _ => Err(SecretParseError::ParseBase32),
+        }
+    }
+
+    /// Will parse a hexadecimal secret, optionally prefixed with `0x` and skipping separators, see [parse](Secret::parse)
+    pub fn parse_hex(input: &str) -> Result<Secret, SecretParseError> {
+        let trimmed = input.trim_start();
+        let (skipped, digits) = match trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+        {
+            Some(digits) => (input[..input.len() - digits.len()].chars().count(), digits),
+            None => (0, input),
+        };
+
+        let mut bytes = zeroize::Zeroizing::new(Vec::with_capacity(digits.len() / 2));
+        let mut high = None;
+        let mut length = 0;
+        for (position, c) in significant(digits) {
+            let nibble = match c.to_digit(16) {
+                Some(nibble) => nibble as u8,
+                None => {
+                    return Err(SecretParseError::InvalidCharacter {
+                        position: skipped + position,
+                        character: c,
+                    })
+                }
+            };
+            length += 1;
+            match high.take() {
+                Some(high) => bytes.push(high << 4 | nibble),
+                None => high = Some(nibble),
+            }
+        }
+
+        if length == 0 {
+            Err(SecretParseError::Empty)
+        } else if high.is_some() {
+            Err(SecretParseError::InvalidLength(length))
+        } else {
+            Ok(Secret::Raw(core::mem::take(&mut *bytes).into()))
+        }
+    }
+
+    /// Will format the secret as lowercase hexadecimal if raw, or as is if encoded, rather than `[REDACTED]`
+    pub fn reveal(&self) -> Reveal<'_, Self> {
+        Reveal(self)
     }
 }
 
-impl core::fmt::Display for Secret {
+/// Will skip separators, keeping the position of each remaining character
+fn significant(input: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    input
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace() && *c != '-')
+}
+
+impl core::fmt::Debug for Secret {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Secret::Raw(bytes) => {
-                for b in bytes {
-                    write!(f, "{:02x}", b)?;
-                }
-                Ok(())
-            }
-            Secret::Encoded(s) => write!(f, "{}", s),
+            Secret::Raw(_) => f.write_str("Raw([REDACTED])"),
+            Secret::Encoded(_) => f.write_str("Encoded([REDACTED])"),
+        }
+    }
+}
+
+impl core::fmt::Display for Secret {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl core::fmt::Display for Reveal<'_, Secret> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Secret::Raw(bytes) => write!(f, "{}", bytes.reveal()),
+            Secret::Encoded(s) => write!(f, "{}", s.reveal()),
         }
     }
 }
@@ -196,6 +427,7 @@ impl core::fmt::Display for Secret {
 #[cfg(test)]
 mod tests {
     use super::Secret;
+    use std::prelude::rust_2021::*;
 
     const BASE32: &str = "OBWGC2LOFVZXI4TJNZTS243FMNZGK5BNGEZDG";
     const BYTES: [u8; 23] = [
@@ -207,18 +439,29 @@ mod tests {
     #[test]
     fn secret_display() {
         let base32_str = String::from(BASE32);
-        let secret_raw = Secret::Raw(BYTES.to_vec());
-        let secret_base32 = Secret::Encoded(base32_str);
+        let secret_raw = Secret::Raw(BYTES.to_vec().into());
+        let secret_base32 = Secret::Encoded(base32_str.into());
         println!("{}", secret_raw);
-        assert_eq!(secret_raw.to_string(), BYTES_DISPLAY.to_string());
-        assert_eq!(secret_base32.to_string(), BASE32.to_string());
+        assert_eq!(secret_raw.to_string(), "[REDACTED]");
+        assert_eq!(secret_base32.to_string(), "[REDACTED]");
+        assert_eq!(secret_raw.reveal().to_string(), BYTES_DISPLAY.to_string());
+        assert_eq!(secret_base32.reveal().to_string(), BASE32.to_string());
+    }
+
+    #[test]
+    fn secret_debug() {
+        let secret_raw = Secret::Raw(BYTES.to_vec().into());
+        let secret_base32 = Secret::Encoded(BASE32.into());
+        assert_eq!(format!("{:?}", secret_raw), "Raw([REDACTED])");
+        assert_eq!(format!("{:?}", secret_base32), "Encoded([REDACTED])");
+        assert_eq!(format!("{:?}", secret_base32.reveal()), BASE32);
     }
 
     #[test]
     fn secret_convert_base32_raw() {
         let base32_str = String::from(BASE32);
-        let secret_raw = Secret::Raw(BYTES.to_vec());
-        let secret_base32 = Secret::Encoded(base32_str);
+        let secret_raw = Secret::Raw(BYTES.to_vec().into());
+        let secret_base32 = Secret::Encoded(base32_str.into());
 
         assert_eq!(&secret_raw.to_encoded(), &secret_base32);
         assert_eq!(&secret_raw.to_raw().unwrap(), &secret_raw);
@@ -231,34 +474,256 @@ mod tests {
     fn secret_as_bytes() {
         let base32_str = String::from(BASE32);
         assert_eq!(
-            Secret::Raw(BYTES.to_vec()).to_bytes().unwrap(),
+            Secret::Raw(BYTES.to_vec().into()).to_bytes().unwrap(),
             BYTES.to_vec()
         );
         assert_eq!(
-            Secret::Encoded(base32_str).to_bytes().unwrap(),
+            Secret::Encoded(base32_str.into()).to_bytes().unwrap(),
             BYTES.to_vec()
         );
     }
 
     #[test]
     fn secret_from_string() {
-        let raw: Secret = Secret::Raw("TestSecretSuperSecret".as_bytes().to_vec());
-        let encoded: Secret = Secret::Encoded("KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ".to_string());
+        let raw: Secret = Secret::Raw("TestSecretSuperSecret".as_bytes().into());
+        let encoded: Secret = Secret::Encoded("KRSXG5CTMVRXEZLUKN2XAZLSKNSWG4TFOQ".into());
         assert_eq!(raw.to_encoded(), encoded);
         assert_eq!(raw, encoded.to_raw().unwrap());
     }
 
+    #[test]
+    fn secret_parse_base32() {
+        for input in [
+            BASE32,
+            "obwgc2lofvzxi4tjnzts243fmnzgk5bngezdg",
+            "OBWG C2LO FVZX I4TJ NZTS 243F MNZG K5BN GEZD G===",
+            "obwg-c2lo-fvzx-i4tj-nzts-243f-mnzg-k5bn-gezd-g",
+            "  OBWGC2LOFVZXI4TJ\n\tNZTS243FMNZGK5BNGEZDG\r\n",
+        ] {
+            let secret = Secret::parse(input).unwrap();
+            assert!(matches!(secret, Secret::Raw(_)));
+            assert_eq!(secret.to_bytes().unwrap(), BYTES, "{}", input);
+            assert_eq!(Secret::parse_base32(input).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn secret_parse_hex() {
+        for input in [
+            BYTES_DISPLAY,
+            "706C61696E2D737472696E672D7365637265742D313233",
+            "0x706c61696e2d737472696e672d7365637265742d313233",
+            " 0X706c 6169 6e2d-7374-7269 6e67 2d73 6563 7265 742d 3132 33",
+        ] {
+            let secret = Secret::parse(input).unwrap();
+            assert_eq!(secret.to_bytes().unwrap(), BYTES, "{}", input);
+            assert_eq!(Secret::parse_hex(input).unwrap(), secret);
+        }
+        // Valid in both, base32 wins unless asked otherwise
+        assert_eq!(Secret::parse("AB").unwrap().to_bytes().unwrap(), [0x00]);
+        assert_eq!(Secret::parse_hex("AB").unwrap().to_bytes().unwrap(), [0xab]);
+    }
+
+    #[test]
+    fn secret_parse_errors() {
+        use super::SecretParseError;
+
+        assert_eq!(Secret::parse(" - \n=="), Err(SecretParseError::Empty));
+        assert_eq!(Secret::parse_hex("0x"), Err(SecretParseError::Empty));
+        assert_eq!(
+            Secret::parse("OBWG C2LO!FVZX"),
+            Err(SecretParseError::InvalidCharacter {
+                position: 9,
+                character: '!'
+            })
+        );
+        assert_eq!(
+            Secret::parse("ébwg c2lo"),
+            Err(SecretParseError::InvalidCharacter {
+                position: 0,
+                character: 'é'
+            })
+        );
+        assert_eq!(
+            Secret::parse("OBWG==C2LO"),
+            Err(SecretParseError::InvalidCharacter {
+                position: 4,
+                character: '='
+            })
+        );
+        assert_eq!(
+            Secret::parse("OBWGC2"),
+            Err(SecretParseError::InvalidLength(6))
+        );
+        assert_eq!(
+            Secret::parse("0123 4"),
+            Err(SecretParseError::InvalidLength(5))
+        );
+        assert_eq!(
+            Secret::parse_hex(" 0x01 23 4g"),
+            Err(SecretParseError::InvalidCharacter {
+                position: 10,
+                character: 'g'
+            })
+        );
+        assert_eq!(
+            SecretParseError::InvalidCharacter {
+                position: 9,
+                character: '!'
+            }
+            .to_string(),
+            "Invalid character '!' at position 9 of secret."
+        );
+        assert_eq!(
+            SecretParseError::InvalidLength(6).to_string(),
+            "Could not decode secret of 6 characters, its length is invalid."
+        );
+    }
+
+    #[test]
+    fn secret_parse_alphabets() {
+        use super::SecretParseError;
+        use crate::Base32Alphabet;
+
+        for alphabet in [
+            Base32Alphabet::RFC4648 { padding: true },
+            Base32Alphabet::RFC4648Hex { padding: false },
+            Base32Alphabet::Crockford,
+            Base32Alphabet::CrockfordCheck,
+            Base32Alphabet::ZBase32,
+        ] {
+            let encoded = base32::encode(alphabet, &BYTES);
+            let secret = Secret::parse_base32_with(&encoded, alphabet).unwrap();
+            assert_eq!(secret.to_bytes().unwrap(), BYTES, "{:?}", alphabet);
+            let spaced = encoded
+                .as_bytes()
+                .chunks(4)
+                .map(|chunk| std::str::from_utf8(chunk).unwrap().to_lowercase())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let secret = Secret::parse_base32_with(&spaced, alphabet).unwrap();
+            assert_eq!(secret.to_bytes().unwrap(), BYTES, "{:?}", alphabet);
+        }
+
+        let check = Base32Alphabet::CrockfordCheck;
+        assert_eq!(
+            Secret::parse_base32_with("28=", check).unwrap().to_bytes(),
+            Ok(vec![0x12])
+        );
+        assert_eq!(
+            Secret::parse_base32_with("CSQP-YRK1-E8R", check)
+                .unwrap()
+                .to_bytes(),
+            Ok(b"foobar".to_vec())
+        );
+        assert_eq!(
+            Secret::parse_base32_with("CSQP-YRK1-E9R", check),
+            Err(SecretParseError::CheckSymbol { position: 12 })
+        );
+        assert_eq!(
+            Secret::parse_base32_with("CSQP-YRK1-E8U", check),
+            Err(SecretParseError::CheckSymbol { position: 12 })
+        );
+        assert_eq!(
+            Secret::parse_base32_with("CSQP-URK1-E8R", check),
+            Err(SecretParseError::InvalidCharacter {
+                position: 5,
+                character: 'U'
+            })
+        );
+        assert_eq!(
+            Secret::parse_base32_with("Z0", check),
+            Err(SecretParseError::InvalidLength(2))
+        );
+        assert_eq!(
+            Secret::parse_base32_with("ZW=N=", check),
+            Err(SecretParseError::InvalidCharacter {
+                position: 2,
+                character: '='
+            })
+        );
+        assert_eq!(
+            Secret::parse_base32_with("c3zs 6aub qv", Base32Alphabet::ZBase32),
+            Err(SecretParseError::InvalidCharacter {
+                position: 11,
+                character: 'v'
+            })
+        );
+        assert_eq!(
+            Secret::parse_base32_with("CPNMUOJ1EW", Base32Alphabet::RFC4648Hex { padding: true }),
+            Err(SecretParseError::InvalidCharacter {
+                position: 9,
+                character: 'W'
+            })
+        );
+        assert_eq!(
+            SecretParseError::CheckSymbol { position: 12 }.to_string(),
+            "Check symbol at position 12 doesn't match the secret, it was likely mistyped."
+        );
+    }
+
+    /// Deterministic stand-in for a CSPRNG, only good enough to test plumbing.
+    #[cfg(feature = "gen_secret")]
+    struct CountingRng(u8);
+
+    #[cfg(feature = "gen_secret")]
+    impl rand::RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for b in dest {
+                self.0 = self.0.wrapping_add(1);
+                *b = self.0;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "gen_secret")]
+    impl rand::CryptoRng for CountingRng {}
+
     #[test]
     #[cfg(feature = "gen_secret")]
     fn secret_gen_secret() {
-        let sec = Secret::generate_secret();
+        let sec = Secret::generate_secret(&mut CountingRng(0));
 
         assert!(matches!(sec, Secret::Raw(_)));
-        assert_eq!(sec.to_bytes().unwrap().len(), 20);
+        assert_eq!(sec.to_bytes().unwrap(), (1..=20).collect::<Vec<u8>>());
     }
 
     #[test]
     #[cfg(feature = "gen_secret")]
+    fn secret_gen_secret_sized() {
+        let mut rng = CountingRng(0);
+        assert_eq!(
+            Secret::generate_secret_sized(&mut rng, 32)
+                .to_bytes()
+                .unwrap()
+                .len(),
+            32
+        );
+        assert_eq!(
+            Secret::generate_secret_sized(&mut rng, 20)
+                .to_bytes()
+                .unwrap(),
+            (33..=52).collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "gen_secret", feature = "std"))]
     fn secret_gen_default() {
         let sec = Secret::default();
 
@@ -270,7 +735,7 @@ mod tests {
     #[cfg(feature = "gen_secret")]
     fn secret_empty() {
         let non_ascii = vec![240, 159, 146, 150];
-        let sec = Secret::Encoded(std::str::from_utf8(&non_ascii).unwrap().to_owned());
+        let sec = Secret::Encoded(std::str::from_utf8(&non_ascii).unwrap().into());
 
         let to_r = sec.to_raw();
 
@@ -0,0 +1,268 @@
+//! Key material that stays out of logs and is wiped from memory once dropped.
+//!
+//! [SecretBytes], and [SecretString] for encoded secrets, print as `[REDACTED]` with both `{}`
+//! and `{:?}`, so a stray format of a [TOTP](crate::TOTP), [Secret](crate::Secret) or
+//! [Rfc6238](crate::Rfc6238) won't leak the key. Reading it takes an explicit
+//! [expose_secret](SecretBytes::expose_secret), and printing it an explicit [reveal](SecretBytes::reveal):
+//!
+//! ```rust
+//! use totp_rs::SecretBytes;
+//!
+//! let secret = SecretBytes::from(b"Secret".to_vec());
+//! assert_eq!(format!("{:?}", secret), "SecretBytes([REDACTED])");
+//! assert_eq!(secret.expose_secret(), b"Secret");
+//! assert_eq!(secret.reveal().to_string(), "536563726574");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use constant_time_eq::constant_time_eq;
+use zeroize::Zeroize;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+/// Raw secret bytes, redacted when formatted and zeroized on drop
+///
+/// Compares in constant time.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(transparent))]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Will give access to the secret itself, be careful where it ends up
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Will format the secret as lowercase hexadecimal, rather than `[REDACTED]`
+    pub fn reveal(&self) -> Reveal<'_, Self> {
+        Reveal(self)
+    }
+
+    /// Number of bytes in the secret
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the secret has no bytes at all
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Will hand the bytes over without copying them, leaving nothing behind to wipe
+    pub(crate) fn into_vec(mut self) -> Vec<u8> {
+        core::mem::take(&mut self.0)
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        SecretBytes(bytes.to_vec())
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretBytes([REDACTED])")
+    }
+}
+
+impl fmt::Display for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Zeroize for SecretBytes {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl zeroize::ZeroizeOnDrop for SecretBytes {}
+
+/// Encoded secret text, such as base32, redacted when formatted and zeroized on drop
+///
+/// Compares in constant time.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(transparent))]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// Will give access to the secret itself, be careful where it ends up
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Will format the secret as is, rather than `[REDACTED]`
+    pub fn reveal(&self) -> Reveal<'_, Self> {
+        Reveal(self)
+    }
+
+    /// Number of bytes in the secret
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the secret has no characters at all
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(s: String) -> Self {
+        SecretString(s)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(s: &str) -> Self {
+        SecretString(s.into())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.0.as_bytes(), other.0.as_bytes())
+    }
+}
+
+impl Eq for SecretString {}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Zeroize for SecretString {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl zeroize::ZeroizeOnDrop for SecretString {}
+
+/// Opt-in formatter printing a secret in clear, see [SecretBytes::reveal], [SecretString::reveal] and [Secret::reveal](crate::Secret::reveal)
+///
+/// `{:?}` prints the same as `{}`.
+pub struct Reveal<'a, T: ?Sized>(pub(crate) &'a T);
+
+impl fmt::Display for Reveal<'_, SecretBytes> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.0.expose_secret() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Reveal<'_, SecretString> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.expose_secret())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Reveal<'_, T>
+where
+    Self: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    #[test]
+    fn redacted() {
+        let secret = SecretBytes::from(b"TestSecretSuperSecret".as_slice());
+        assert_eq!(format!("{:?}", secret), "SecretBytes([REDACTED])");
+        assert_eq!(format!("{:#?}", secret), "SecretBytes([REDACTED])");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert_eq!(secret.len(), 21);
+    }
+
+    #[test]
+    fn revealed() {
+        let secret = SecretBytes::from(vec![0x00, 0x0f, 0xa0, 0xff]);
+        assert_eq!(secret.expose_secret(), [0x00, 0x0f, 0xa0, 0xff]);
+        assert_eq!(secret.reveal().to_string(), "000fa0ff");
+        assert_eq!(format!("{:?}", secret.reveal()), "000fa0ff");
+    }
+
+    #[test]
+    fn zeroized() {
+        let mut secret = SecretBytes::from(vec![1, 2, 3]);
+        secret.zeroize();
+        assert!(secret.is_empty());
+        assert_eq!(SecretBytes::from(vec![1, 2, 3]).into_vec(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn strings() {
+        let mut secret = SecretString::from("KRSXG5CTMVRXEZLU");
+        assert_eq!(format!("{:?}", secret), "SecretString([REDACTED])");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert_eq!(secret.expose_secret(), "KRSXG5CTMVRXEZLU");
+        assert_eq!(secret.reveal().to_string(), "KRSXG5CTMVRXEZLU");
+        assert_eq!(secret, SecretString::from(String::from("KRSXG5CTMVRXEZLU")));
+        assert_ne!(secret, SecretString::from("KRSXG5CTMVRXEZLV"));
+        secret.zeroize();
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn compares() {
+        assert_eq!(
+            SecretBytes::from(vec![1, 2, 3]),
+            SecretBytes::from(vec![1, 2, 3])
+        );
+        assert_ne!(
+            SecretBytes::from(vec![1, 2, 3]),
+            SecretBytes::from(vec![1, 2, 4])
+        );
+        assert_ne!(SecretBytes::from(vec![1, 2, 3]), SecretBytes::default());
+    }
+}
@@ -0,0 +1,97 @@
+//! Where the `*_with` methods of [TOTP](crate::TOTP) get the current time from.
+//!
+//! With feature `std`, [SystemTimeSource] reads the system clock, which is what the
+//! `*_current` methods use. Without it, implement [TimeSource] on top of whatever clock
+//! the platform has, or use a closure:
+//!
+//! ```rust
+//! use totp_rs::TOTP;
+//!
+//! let totp = TOTP::builder().secret(b"TestSecretSuperSecret".to_vec()).build().unwrap();
+//! let firmware_clock = || Ok::<u64, ()>(1000);
+//! assert_eq!(totp.generate_current_with(&firmware_clock), Ok(totp.generate(1000)));
+//! ```
+
+#[cfg(feature = "std")]
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+
+/// A clock giving the current Unix time, in seconds
+pub trait TimeSource {
+    type Error;
+
+    fn now(&self) -> Result<u64, Self::Error>;
+}
+
+/// Any closure returning the current Unix time, in seconds
+impl<F, E> TimeSource for F
+where
+    F: Fn() -> Result<u64, E>,
+{
+    type Error = E;
+
+    fn now(&self) -> Result<u64, E> {
+        self()
+    }
+}
+
+/// The system clock
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+#[cfg(feature = "std")]
+impl TimeSource for SystemTimeSource {
+    type Error = SystemTimeError;
+
+    fn now(&self) -> Result<u64, SystemTimeError> {
+        Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+    }
+}
+
+/// A clock stopped at the given Unix time, in seconds, mostly useful in tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedTime(pub u64);
+
+impl TimeSource for FixedTime {
+    type Error = core::convert::Infallible;
+
+    fn now(&self) -> Result<u64, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn fixed_time() {
+        assert_eq!(FixedTime(1000).now(), Ok(1000));
+    }
+
+    #[test]
+    fn closure_time() {
+        let ticks = Cell::new(0);
+        let clock = || {
+            ticks.set(ticks.get() + 30);
+            Ok::<u64, ()>(ticks.get())
+        };
+        assert_eq!(clock.now(), Ok(30));
+        assert_eq!(clock.now(), Ok(60));
+
+        let broken = || Err::<u64, &str>("no clock");
+        assert_eq!(broken.now(), Err("no clock"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn system_time() {
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let now = SystemTimeSource.now().unwrap();
+        assert!(now >= before && now <= before + 1);
+    }
+}
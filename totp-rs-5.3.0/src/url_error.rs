@@ -1,6 +1,3 @@
-#[cfg(feature = "otpauth")]
-use url::ParseError;
-
 use alloc::string::String;
 
 use crate::Rfc6238Error;
@@ -10,7 +7,7 @@ use crate::Rfc6238Error;
 pub enum TotpUrlError {
     /// Couldn't decode URL.
     #[cfg(feature = "otpauth")]
-    Url(ParseError),
+    Url(UrlParseError),
     /// Invalid scheme.
     Scheme(String),
     /// Invalid host.
@@ -27,6 +24,10 @@ pub enum TotpUrlError {
     DigitsNumber(usize),
     /// Couldn't decode step into a number.
     Step(String),
+    /// Couldn't decode T0 into a number.
+    T0(String),
+    /// Couldn't decode an `x-symbols`, `x-group` or `x-separator` parameter.
+    Encoding(String),
     /// Issuer contains invalid character `:`.
     Issuer(String),
     /// Couldn't decode issuer.
@@ -41,6 +42,29 @@ pub enum TotpUrlError {
 
 impl core::error::Error for TotpUrlError {}
 
+/// Why a string could not be split into the parts of a URL.
+#[cfg(feature = "otpauth")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UrlParseError {
+    /// There is nothing between `://` and the path.
+    EmptyHost,
+    /// The URL does not start with a scheme.
+    RelativeUrlWithoutBase,
+}
+
+#[cfg(feature = "otpauth")]
+impl core::error::Error for UrlParseError {}
+
+#[cfg(feature = "otpauth")]
+impl core::fmt::Display for UrlParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UrlParseError::EmptyHost => f.write_str("empty host"),
+            UrlParseError::RelativeUrlWithoutBase => f.write_str("relative URL without a base"),
+        }
+    }
+}
+
 impl core::fmt::Display for TotpUrlError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -56,7 +80,7 @@ impl core::fmt::Display for TotpUrlError {
             ),
             TotpUrlError::Algorithm(algo) => write!(
                 f,
-                "Algorithm can only be SHA1, SHA256 or SHA512, not \"{}\"",
+                "Algorithm can only be SHA1, SHA224, SHA256, SHA384, SHA512, SHA512_224 or SHA512_256, not \"{}\"",
                 algo
             ),
             TotpUrlError::Digits(digits) => write!(
@@ -110,6 +134,16 @@ impl core::fmt::Display for TotpUrlError {
                 "Could not parse \"{}\" as a number.",
                 step,
             ),
+            TotpUrlError::T0(t0) => write!(
+                f,
+                "Could not parse \"{}\" as a number.",
+                t0,
+            ),
+            TotpUrlError::Encoding(value) => write!(
+                f,
+                "Could not parse \"{}\" as part of a token encoding.",
+                value,
+            ),
             #[cfg(feature = "otpauth")]
             TotpUrlError::Url(e) => write!(
                 f,
@@ -132,6 +166,7 @@ impl From<Rfc6238Error> for TotpUrlError {
 #[cfg(test)]
 mod tests {
     use crate::TotpUrlError;
+    use std::prelude::rust_2021::*;
 
     #[test]
     fn account_name() {
@@ -156,7 +191,7 @@ mod tests {
         let error = TotpUrlError::Algorithm("SIKE".to_string());
         assert_eq!(
             error.to_string(),
-            "Algorithm can only be SHA1, SHA256 or SHA512, not \"SIKE\"".to_string()
+            "Algorithm can only be SHA1, SHA224, SHA256, SHA384, SHA512, SHA512_224 or SHA512_256, not \"SIKE\"".to_string()
         )
     }
 
@@ -236,10 +271,28 @@ mod tests {
         )
     }
 
+    #[test]
+    fn t0() {
+        let error = TotpUrlError::T0("-1".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Could not parse \"-1\" as a number.".to_string()
+        )
+    }
+
+    #[test]
+    fn encoding() {
+        let error = TotpUrlError::Encoding("0".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Could not parse \"0\" as part of a token encoding.".to_string()
+        )
+    }
+
     #[test]
     #[cfg(feature = "otpauth")]
     fn step() {
-        let error = TotpUrlError::Url(url::ParseError::EmptyHost);
+        let error = TotpUrlError::Url(crate::UrlParseError::EmptyHost);
         assert_eq!(
             error.to_string(),
             "Error parsing URL: empty host".to_string()
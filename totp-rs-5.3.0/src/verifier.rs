@@ -0,0 +1,334 @@
+//! Stateful verification of tokens: each one is accepted once, and guessing gets locked out.
+//!
+//! [TOTP::check](crate::TOTP::check) will accept the same token for as long as it is valid, as often
+//! as it is given. A [Verifier] remembers, in a [VerifierState] kept per account, the last step it
+//! accepted and how many tokens in a row were wrong. The state is plain data, to be stored wherever
+//! the account is, and with feature `serde_support` it can be serialized.
+//!
+//! ```rust
+//! use totp_rs::{TOTP, Verifier, VerifierState, VerifyError};
+//!
+//! let totp = TOTP::builder()
+//!     .secret(b"TestSecretSuperSecret".to_vec())
+//!     .build()
+//!     .unwrap();
+//! let verifier = Verifier::default();
+//! let mut state = VerifierState::default();
+//!
+//! let token = totp.generate(1000);
+//! assert!(verifier.verify(&totp, &mut state, &token, 1000).is_ok());
+//! assert_eq!(verifier.verify(&totp, &mut state, &token, 1010), Err(VerifyError::Replayed));
+//! ```
+
+use core::fmt;
+
+use crate::{CheckedStep, TimeSource, TOTP};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::SystemTimeSource;
+#[cfg(feature = "std")]
+use std::time::SystemTimeError;
+
+/// Policy of a [Verifier], the same for every account
+///
+/// Defaults to one step in the past and one in the future, and locks out for 5 minutes after 5 wrong tokens in a row.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Verifier {
+    /// Number of steps before the current one still accepted, for slow typists and networks
+    pub past: u8,
+    /// Number of steps after the current one already accepted, for clocks running ahead
+    pub future: u8,
+    /// Number of wrong tokens in a row that lock the account out. 0 never locks it
+    pub max_failures: u32,
+    /// Duration in seconds of a lockout
+    pub lockout: u64,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Verifier {
+            past: 1,
+            future: 1,
+            max_failures: 5,
+            lockout: 300,
+        }
+    }
+}
+
+/// What a [Verifier] remembers about one account, to be persisted between verifications
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct VerifierState {
+    /// The last step a token was accepted for. Tokens for it or an earlier step are rejected
+    pub last_step: Option<u64>,
+    /// Number of wrong tokens since the last accepted one or lockout
+    pub failures: u32,
+    /// Unix time until which every token is rejected
+    pub locked_until: Option<u64>,
+}
+
+/// Why a [Verifier] rejected a token
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VerifyError {
+    /// The token is not valid in any window, which counts as a failure.
+    Invalid,
+    /// The token was valid, but for a step a token was already accepted for.
+    Replayed,
+    /// Too many wrong tokens, none is checked until the given Unix time.
+    Locked { until: u64 },
+}
+
+impl core::error::Error for VerifyError {}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Invalid => f.write_str("Token is invalid."),
+            VerifyError::Replayed => f.write_str("Token was already used."),
+            VerifyError::Locked { until } => {
+                write!(f, "Too many invalid tokens, locked until {}.", until)
+            }
+        }
+    }
+}
+
+impl Verifier {
+    /// Will create a verifier accepting `past` steps before the current one and `future` steps after it, with the default lockout
+    pub fn new(past: u8, future: u8) -> Verifier {
+        Verifier {
+            past,
+            future,
+            ..Verifier::default()
+        }
+    }
+
+    /// Will check the token given the provided timestamp in seconds, and update the state of the account
+    ///
+    /// On success, returns the step the token was accepted for, the earliest after [last_step](VerifierState::last_step)
+    /// if it matches several. [skew](struct.TOTP.html#structfield.skew) is ignored in favour of [past](Verifier::past) and [future](Verifier::future).
+    ///
+    /// # Errors
+    ///
+    /// Will return [VerifyError::Locked] while locked out, without checking the token.
+    /// Otherwise [VerifyError::Replayed] if it was valid but not after the last accepted step, or [VerifyError::Invalid].
+    pub fn verify(
+        &self,
+        totp: &TOTP,
+        state: &mut VerifierState,
+        token: &str,
+        time: u64,
+    ) -> Result<CheckedStep, VerifyError> {
+        match state.locked_until {
+            Some(until) if time < until => return Err(VerifyError::Locked { until }),
+            Some(_) => state.locked_until = None,
+            None => {}
+        }
+
+        let last_step = state.last_step;
+        let mut replayed = false;
+        let mut accepted = None;
+        totp.prepare()
+            .check_window(token, time, self.past, self.future, |checked| {
+                if last_step.map_or(true, |last| checked.step > last) {
+                    accepted = accepted.or(Some(checked));
+                } else {
+                    replayed = true;
+                }
+            });
+
+        match accepted {
+            Some(checked) => {
+                state.last_step = Some(checked.step);
+                state.failures = 0;
+                Ok(checked)
+            }
+            None if replayed => Err(VerifyError::Replayed),
+            None => {
+                state.failures = state.failures.saturating_add(1);
+                if self.max_failures > 0 && state.failures >= self.max_failures {
+                    state.failures = 0;
+                    state.locked_until = Some(time.saturating_add(self.lockout));
+                }
+                Err(VerifyError::Invalid)
+            }
+        }
+    }
+
+    /// Same as [verify](Verifier::verify), by current system time
+    #[cfg(feature = "std")]
+    pub fn verify_current(
+        &self,
+        totp: &TOTP,
+        state: &mut VerifierState,
+        token: &str,
+    ) -> Result<Result<CheckedStep, VerifyError>, SystemTimeError> {
+        self.verify_current_with(totp, state, token, &SystemTimeSource)
+    }
+
+    /// Same as [verify](Verifier::verify), at the time given by a [TimeSource]
+    pub fn verify_current_with<T: TimeSource>(
+        &self,
+        totp: &TOTP,
+        state: &mut VerifierState,
+        token: &str,
+        time: &T,
+    ) -> Result<Result<CheckedStep, VerifyError>, T::Error> {
+        let t = time.now()?;
+        Ok(self.verify(totp, state, token, t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::rust_2021::*;
+
+    fn totp() -> TOTP {
+        TOTP::builder()
+            .secret(b"TestSecretSuperSecret".to_vec())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn rejects_replays() {
+        let totp = totp();
+        let verifier = Verifier::default();
+        let mut state = VerifierState::default();
+        let token = totp.generate(1000);
+
+        let checked = verifier.verify(&totp, &mut state, &token, 1000).unwrap();
+        assert_eq!(checked.offset, 0);
+        assert_eq!(state.last_step, Some(33));
+        assert_eq!(
+            verifier.verify(&totp, &mut state, &token, 1000),
+            Err(VerifyError::Replayed)
+        );
+        assert_eq!(
+            verifier.verify(&totp, &mut state, &token, 1030),
+            Err(VerifyError::Replayed)
+        );
+        // An older token, still in the window, is no better
+        assert_eq!(
+            verifier.verify(&totp, &mut state, &totp.generate(970), 1000),
+            Err(VerifyError::Replayed)
+        );
+        assert_eq!(state.failures, 0);
+
+        let next = verifier
+            .verify(&totp, &mut state, &totp.generate(1030), 1000)
+            .unwrap();
+        assert_eq!(next.offset, 1);
+        assert_eq!(state.last_step, Some(34));
+    }
+
+    #[test]
+    fn asymmetric_windows() {
+        let totp = totp();
+        let verifier = Verifier::new(2, 0);
+        for (time, valid) in [
+            (910, false),
+            (940, true),
+            (970, true),
+            (1000, true),
+            (1030, false),
+        ] {
+            let mut state = VerifierState::default();
+            let result = verifier.verify(&totp, &mut state, &totp.generate(time), 1000);
+            assert_eq!(result.is_ok(), valid, "{}", time);
+        }
+        let checked = verifier
+            .verify(
+                &totp,
+                &mut VerifierState::default(),
+                &totp.generate(940),
+                1000,
+            )
+            .unwrap();
+        assert_eq!(checked.offset, -2);
+        assert_eq!(checked.step, 31);
+    }
+
+    #[test]
+    fn locks_out() {
+        let totp = totp();
+        let verifier = Verifier {
+            max_failures: 3,
+            lockout: 60,
+            ..Verifier::default()
+        };
+        let mut state = VerifierState::default();
+        for failures in 1..3 {
+            assert_eq!(
+                verifier.verify(&totp, &mut state, "000000", 1000),
+                Err(VerifyError::Invalid)
+            );
+            assert_eq!(state.failures, failures);
+        }
+        assert_eq!(
+            verifier.verify(&totp, &mut state, "000000", 1000),
+            Err(VerifyError::Invalid)
+        );
+        assert_eq!(state.locked_until, Some(1060));
+
+        // Even the right token waits
+        let token = totp.generate(1050);
+        assert_eq!(
+            verifier.verify(&totp, &mut state, &token, 1050),
+            Err(VerifyError::Locked { until: 1060 })
+        );
+        assert!(verifier.verify(&totp, &mut state, &token, 1060).is_ok());
+        assert_eq!(
+            state,
+            VerifierState {
+                last_step: Some(35),
+                failures: 0,
+                locked_until: None,
+            }
+        );
+    }
+
+    #[test]
+    fn success_resets_failures() {
+        let totp = totp();
+        let verifier = Verifier::default();
+        let mut state = VerifierState::default();
+        assert!(verifier.verify(&totp, &mut state, "000000", 1000).is_err());
+        assert_eq!(state.failures, 1);
+        assert!(verifier
+            .verify(&totp, &mut state, &totp.generate(1000), 1000)
+            .is_ok());
+        assert_eq!(state.failures, 0);
+
+        let never = Verifier {
+            max_failures: 0,
+            ..Verifier::default()
+        };
+        let mut state = VerifierState::default();
+        for _ in 0..100 {
+            assert!(never.verify(&totp, &mut state, "000000", 1000).is_err());
+        }
+        assert_eq!(state.locked_until, None);
+    }
+
+    #[test]
+    fn with_time_source() {
+        let totp = totp();
+        let token = totp.generate(1000);
+        let result = Verifier::default().verify_current_with(
+            &totp,
+            &mut VerifierState::default(),
+            &token,
+            &crate::FixedTime(1000),
+        );
+        assert!(matches!(result, Ok(Ok(_))));
+        assert_eq!(
+            VerifyError::Locked { until: 1060 }.to_string(),
+            "Too many invalid tokens, locked until 1060."
+        );
+    }
+}